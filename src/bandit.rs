@@ -0,0 +1,63 @@
+use crate::board::*;
+use crate::graph::*;
+use crate::opponents::*;
+
+// Chooses where to put the bandit. The bandit should block as much production as possible of the
+// opponent that is closest to winning, without blocking ourselves.
+
+// how much blocking a pip of the leader counts compared to a pip of another opponent
+const LEADER_WEIGHT: f64 = 3.0;
+// how much blocking one of our own pips costs
+const OWN_WEIGHT: f64 = 4.0;
+
+pub struct BanditTarget<'a> {
+    pub tile: &'a Tile,
+    // the player we want to steal from, the server picks the victim itself at the moment so this
+    // is only informative
+    pub victim: Option<usize>,
+    pub score: f64,
+}
+
+// The best of the given tile keys to put the bandit on, against the leading opponent. Tiles next
+// to our own buildings are only chosen when there is nothing else.
+pub fn get_best_bandit_target<'a>(graph: &'a BoardGraph, me: usize, leader: Option<usize>, locations: &[String], hands: &OpponentModel) -> Option<BanditTarget<'a>> {
+    let targets = locations.iter()
+        .filter_map(|location| graph.get_tile_by_key(location))
+        .map(|tile| {
+            let mut score = 0.0;
+            for node in graph.get_nodes_surrounding_tile(tile) {
+                let player = match node.player {
+                    Some(player) => player,
+                    None => continue,
+                };
+                let amount = if node.structure == Some(StructureKind::City) { 2.0 } else { 1.0 };
+                let weight = if player == me {
+                    -OWN_WEIGHT
+                } else if Some(player) == leader {
+                    LEADER_WEIGHT
+                } else {
+                    1.0
+                };
+                score += weight * amount * tile.get_pips() as f64;
+            }
+            BanditTarget { tile, victim: get_victim(graph, tile, me, leader, hands), score }
+        });
+
+    // the tiles next to us come last, whatever their score
+    targets.max_by(|a, b| {
+        let touches_me = |target: &BanditTarget| graph.get_nodes_surrounding_tile(target.tile).iter().any(|node| node.player == Some(me));
+        touches_me(b).cmp(&touches_me(a)).then(a.score.total_cmp(&b.score))
+    })
+}
+
+// Steal from the leader if it is next to the tile, otherwise from the neighbour with the most cards
+fn get_victim(graph: &BoardGraph, tile: &Tile, me: usize, leader: Option<usize>, hands: &OpponentModel) -> Option<usize> {
+    let neighbours: Vec<usize> = graph.get_nodes_surrounding_tile(tile).into_iter()
+        .filter_map(|node| node.player)
+        .filter(|player| *player != me)
+        .collect();
+    if leader.is_some_and(|leader| neighbours.contains(&leader)) {
+        return leader
+    }
+    neighbours.into_iter().max_by_key(|player| hands.get_hand(*player).map_or(0, |hand| hand.get_total()))
+}
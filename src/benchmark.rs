@@ -0,0 +1,96 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use log::info;
+
+use crate::engine::*;
+use crate::graph::*;
+use crate::strategy::*;
+
+// Compares the board queries that scan the Board with the ones that use the BoardGraph, on a
+// full 19 tile board in the middle of a game.
+
+const ROUNDS_BEFORE_MEASURING: u32 = 30;
+
+pub fn run_benchmarks(iterations: u32, seed: u64) {
+    let strategies: Vec<Box<dyn Strategy>> = (0..4).map(|player| {
+        Box::new(RandomStrategy::new(seed.wrapping_add(player))) as Box<dyn Strategy>
+    }).collect();
+    let mut local_game = LocalGame::new(strategies, seed);
+    local_game.place_initial_buildings();
+    for _ in 0..ROUNDS_BEFORE_MEASURING {
+        local_game.play_round();
+    }
+    let game = local_game.snapshot(0);
+    let board = game.get_board().unwrap();
+    let graph = BoardGraph::new(board.clone());
+    let player = game.me().unwrap();
+    info!("Measuring {} iterations on a board with {} tiles, {} nodes and {} edges",
+        iterations, board.get_tiles().len(), board.get_nodes().len(), board.get_edges().len());
+
+    // the graph takes the board over, so copying it is part of building one
+    let build_time = time(iterations, || { black_box(BoardGraph::new(board.clone())); });
+    info!("{:<28} {:>10.2?}", "building the graph", build_time / iterations);
+    measure("get_tile_by_key", iterations, || {
+        for tile in board.get_tiles() {
+            black_box(board.get_tile_by_key(&tile.key));
+        }
+    }, || {
+        for tile in graph.get_tiles() {
+            black_box(graph.get_tile_by_key(&tile.key));
+        }
+    });
+    measure("get_edges_surrounding_node", iterations, || {
+        for node in board.get_nodes() {
+            black_box(board.get_edges_surrounding_node(node));
+        }
+    }, || {
+        for node in graph.get_nodes() {
+            black_box(graph.get_edges_surrounding_node(node));
+        }
+    });
+    measure("get_nodes_surrounding_edge", iterations, || {
+        for edge in board.get_edges() {
+            black_box(board.get_nodes_surrounding_edge(edge));
+        }
+    }, || {
+        for edge in graph.get_edges() {
+            black_box(graph.get_nodes_surrounding_edge(edge));
+        }
+    });
+    measure("get_nodes_surrounding_node", iterations, || {
+        for node in board.get_nodes() {
+            black_box(board.get_nodes_surrounding_node(node));
+        }
+    }, || {
+        for node in graph.get_nodes() {
+            black_box(graph.get_nodes_surrounding_node(node));
+        }
+    });
+    measure("get_potential_street_edges", iterations, || {
+        black_box(board.get_potential_street_edges(player));
+    }, || {
+        black_box(graph.get_potential_street_edges(player));
+    });
+    measure("get_potential_village_nodes", iterations, || {
+        black_box(board.get_potential_village_nodes(player));
+    }, || {
+        black_box(graph.get_potential_village_nodes(player));
+    });
+}
+
+// time both versions of a query and log how much faster the graph is
+fn measure<F: FnMut(), G: FnMut()>(name: &str, iterations: u32, board_query: F, graph_query: G) {
+    let board_time = time(iterations, board_query);
+    let graph_time = time(iterations, graph_query);
+    let speed_up = board_time.as_secs_f64() / graph_time.as_secs_f64().max(f64::EPSILON);
+    info!("{:<28} board: {:>10.2?}  graph: {:>10.2?}  speed-up: {:.1}x",
+        name, board_time / iterations, graph_time / iterations, speed_up);
+}
+
+fn time<F: FnMut()>(iterations: u32, mut query: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        query();
+    }
+    start.elapsed()
+}
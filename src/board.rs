@@ -1,27 +1,40 @@
 use serde::{Deserialize, Serialize};
 use itertools::Itertools;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use log::warn;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputBoard {
     pub model: String,
     pub attributes: Board,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Board {
     #[serde(default)]
-    bandits: Vec<ServerInputBandit>,
+    pub bandits: Vec<ServerInputBandit>,
     #[serde(default)]
-    tiles: Vec<ServerInputTile>,
+    pub tiles: Vec<ServerInputTile>,
     #[serde(default)]
-    nodes: Vec<ServerInputNode>,
+    pub nodes: Vec<ServerInputNode>,
     #[serde(default)]
-    edges: Vec<ServerInputEdge>,
+    pub edges: Vec<ServerInputEdge>,
+    #[serde(default)]
+    pub ports: Vec<ServerInputPort>,
 }
 
 impl Board {
+    pub fn new(tiles: Vec<Tile>, nodes: Vec<Node>, edges: Vec<Edge>, bandits: Vec<Bandit>, ports: Vec<Port>) -> Board {
+        Board {
+            bandits: bandits.into_iter().map(|bandit| ServerInputBandit { model: String::from("bandit"), attributes: bandit }).collect(),
+            tiles: tiles.into_iter().map(|tile| ServerInputTile { model: String::from("tile"), attributes: tile }).collect(),
+            nodes: nodes.into_iter().map(|node| ServerInputNode { model: String::from("node"), attributes: node }).collect(),
+            edges: edges.into_iter().map(|edge| ServerInputEdge { model: String::from("edge"), attributes: edge }).collect(),
+            ports: ports.into_iter().map(|port| ServerInputPort { model: String::from("port"), attributes: port }).collect(),
+        }
+    }
+
     pub fn get_tiles(&self) -> Vec<&Tile> {
         self.tiles.iter().map(|server_tile| { &server_tile.attributes }).collect()
     }
@@ -34,12 +47,75 @@ impl Board {
         self.edges.iter().map(|server_edge| { &server_edge.attributes}).collect()
     }
 
-    pub fn get_tile_by_key(&self, key: &str) -> Option<&Tile> {
-        self.get_tiles().into_iter().find(|tile| tile.key == key)
+    pub fn get_bandits(&self) -> Vec<&Bandit> {
+        self.bandits.iter().map(|server_bandit| { &server_bandit.attributes }).collect()
+    }
+
+    pub fn get_ports(&self) -> Vec<&Port> {
+        self.ports.iter().map(|server_port| { &server_port.attributes }).collect()
+    }
+
+    // The server does not always send ports, in that case they are put on the coast following the
+    // layout. The coast are the edges between a land tile and the sea, found from the nodes with
+    // fewer than three land tiles, so it does not matter whether the board has water tiles.
+    pub fn add_default_ports(&mut self, layout: PortLayout) {
+        if !self.ports.is_empty() || layout == PortLayout::None {
+            return
+        }
+
+        // the axial x and y of the tiles as positions on the plane, ordered around the center
+        let position = |tile: &Tile| (tile.x as f64 + tile.y as f64 / 2.0, tile.y as f64 * 3f64.sqrt() / 2.0);
+        let land: HashMap<&str, &Tile> = self.get_tiles().into_iter()
+            .filter(|tile| tile.resource_type.is_land())
+            .map(|tile| (tile.key.as_str(), tile))
+            .collect();
+        let edge_keys: HashSet<&str> = self.get_edges().into_iter().map(|edge| edge.key.as_str()).collect();
+
+        // every coast edge with the land tiles of the nodes at its ends, they lie in its direction
+        let mut coast: HashMap<&str, Vec<(f64, f64)>> = HashMap::new();
+        for node in self.get_nodes() {
+            let keys = [node.t_key.as_str(), node.l_key.as_str(), node.r_key.as_str()];
+            let positions: Vec<(f64, f64)> = keys.iter().filter_map(|key| land.get(key)).map(|tile| position(tile)).collect();
+            if positions.len() == keys.len() {
+                continue
+            }
+            for (a, b) in keys.iter().tuple_combinations() {
+                if land.contains_key(a) == land.contains_key(b) {
+                    continue
+                }
+                let edge_key = [format!("({},{})", a, b), format!("({},{})", b, a)].iter()
+                    .find_map(|key| edge_keys.get(key.as_str()).cloned());
+                if let Some(edge_key) = edge_key {
+                    coast.entry(edge_key).or_default().extend(positions.iter().cloned());
+                }
+            }
+        }
+
+        let mean = |positions: &[(f64, f64)]| {
+            let count = positions.len().max(1) as f64;
+            (positions.iter().map(|(x, _)| x).sum::<f64>() / count, positions.iter().map(|(_, y)| y).sum::<f64>() / count)
+        };
+        let center = mean(&land.values().map(|tile| position(tile)).collect::<Vec<(f64, f64)>>());
+        let angle = |positions: &[(f64, f64)]| {
+            let (x, y) = mean(positions);
+            (y - center.1).atan2(x - center.0)
+        };
+        let mut coast: Vec<(&str, f64)> = coast.iter().map(|(key, positions)| (*key, angle(positions))).collect();
+        coast.sort_by(|(a_key, a), (b_key, b)| a.total_cmp(b).then(a_key.cmp(b_key)));
+
+        // the ports are spread evenly around the coast
+        let count = STANDARD_PORTS.len().min(coast.len());
+        let ports: Vec<Port> = (0..count).zip(STANDARD_PORTS.iter()).map(|(i, kind)| {
+            Port { edge_key: String::from(coast[i * coast.len() / count].0), kind: *kind, ratio: None }
+        }).collect();
+        if ports.is_empty() {
+            warn!("The board has no coast, no ports were placed");
+        }
+        self.ports = ports.into_iter().map(|port| ServerInputPort { model: String::from("port"), attributes: port }).collect();
     }
 
-    pub fn get_node_by_key(&self, key: &str) -> Option<&Node> {
-        self.get_nodes().into_iter().find(|node| node.key == key)
+    pub fn get_tile_by_key(&self, key: &str) -> Option<&Tile> {
+        self.get_tiles().into_iter().find(|tile| tile.key == key)
     }
 
     pub fn get_edge_by_key(&self, key: &str) -> Option<&Edge> {
@@ -88,7 +164,7 @@ impl Board {
     pub fn get_nodes_from_player(&self, player: &Player) -> Vec<&Node> {
         self.get_nodes().into_iter().filter(|node| {
             if let Some(player_id) = node.player {
-                return player_id == player.id && node.structure != ""
+                return player_id == player.id && node.structure.is_some()
             }
             false
         }).collect()
@@ -97,20 +173,37 @@ impl Board {
     pub fn get_cities_from_player(&self, player: &Player) -> Vec<&Node> {
         let nodes = self.get_nodes_from_player(player);
         nodes.into_iter().filter(|node| {
-            node.structure == "city"
+            node.structure == Some(StructureKind::City)
         }).collect()
     }
 
     pub fn get_villages_from_player(&self, player: &Player) -> Vec<&Node> {
         let nodes = self.get_nodes_from_player(player);
         nodes.into_iter().filter(|node| {
-            node.structure == "village"
+            node.structure == Some(StructureKind::Village)
         }).collect()
     }
 
+    // the ports on the edges next to the node
+    pub fn get_ports_of_node(&self, node: &Node) -> Vec<&Port> {
+        let edges = self.get_edges_surrounding_node(node);
+        self.get_ports().into_iter().filter(|port| edges.iter().any(|edge| edge.key == port.edge_key)).collect()
+    }
+
+    // the ports a player can trade with, the ones next to one of its villages or cities
+    pub fn get_ports_for_player(&self, player: &Player) -> Vec<&Port> {
+        let mut ports: Vec<&Port> = Vec::new();
+        for port in self.get_nodes_from_player(player).into_iter().flat_map(|node| self.get_ports_of_node(node)) {
+            if !ports.contains(&port) {
+                ports.push(port);
+            }
+        }
+        ports
+    }
+
     pub fn get_edges_from_player(&self, player: &Player) -> Vec<&Edge> {
         self.get_edges().into_iter().filter(|edge| {
-            edge.road // && edge.player.id == player.id
+            edge.road && edge.player == Some(player.id)
         }).collect()
     }
 
@@ -118,10 +211,12 @@ impl Board {
     pub fn get_potential_street_edges(&self, player: &Player) -> Vec<&Edge> {
         let player_streets = self.get_edges_from_player(player);
 
-        // get the nodes around the players streets
+        // get the nodes around the players streets, a village of an opponent breaks the road
         let nodes_connected_to_player_streets = player_streets.into_iter().map(|street| {
             self.get_nodes_surrounding_edge(street)
-        }).concat();
+        }).concat().into_iter().filter(|node| {
+            node.player.is_none() || node.player == Some(player.id)
+        });
 
         // get all edges surrounding the nodes to which the player has at least one edge
         let edges_around_nodes: Vec<&Edge> = nodes_connected_to_player_streets.into_iter().map(|node| {
@@ -159,47 +254,59 @@ impl Board {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputTile {
     pub model: String,
     pub attributes: Tile,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Tile {
     pub key: String,
-    pub resource_type: String,
+    pub resource_type: TileKind,
     pub number: u32,
     pub x: u8,
     pub y: u8,
 }
 
-#[derive(Serialize, Deserialize)]
+impl Tile {
+    // The number of dice combinations that make the tile produce, between 0 and 5
+    pub fn get_pips(&self) -> u32 {
+        match self.number {
+            2..=6 => self.number - 1,
+            8..=12 => 13 - self.number,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputNode {
     pub model: String,
     pub attributes: Node,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Node {
     pub key: String,
-    pub structure: String,
+    #[serde(with = "optional_structure")]
+    pub structure: Option<StructureKind>,
     pub player: Option<usize>,
     pub t_key: String,
     pub r_key: String,
     pub l_key: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputEdge {
     pub model: String,
     pub attributes: Edge,
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Debug)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
 pub struct Edge {
     pub key: String,
-    pub player: Option<u8>,
+    pub player: Option<usize>,
     pub road: bool,
 }
 
@@ -209,35 +316,367 @@ impl Ord for Edge {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+impl PartialOrd for Edge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputPlayer {
     pub model: String,
     pub attributes: Player,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Player {
     pub id: usize,
     pub color: String,
     pub name: String,
     pub resources: Vec<Resource>,
-//    pub development_cards: Vec<String>,
+    // the server only shows the cards of our own player
+    #[serde(default)]
+    pub development_cards: Vec<DevelopmentCardKind>,
+    // the knights the player has played, for the largest army
+    #[serde(default)]
+    pub played_knights: usize,
 }
 
+impl Player {
+    pub fn get_resource_count(&self, kind: ResourceKind) -> usize {
+        self.resources.iter().filter(|resource| resource.r#type == kind).map(|resource| resource.value).sum()
+    }
+
+    pub fn get_resource_total(&self) -> usize {
+        self.resources.iter().map(|resource| resource.value).sum()
+    }
+
+    pub fn can_afford(&self, structure: StructureKind) -> bool {
+        self.missing_for(structure).is_empty()
+    }
+
+    pub fn can_afford_development_card(&self) -> bool {
+        DEVELOPMENT_CARD_COST.iter().all(|(kind, amount)| self.get_resource_count(*kind) >= *amount)
+    }
+
+    pub fn get_development_card_count(&self, kind: DevelopmentCardKind) -> usize {
+        self.development_cards.iter().filter(|card| **card == kind).count()
+    }
+
+    // the resources the player still needs to collect before the structure can be built
+    pub fn missing_for(&self, structure: StructureKind) -> Vec<Resource> {
+        structure.get_cost().into_iter().filter_map(|(kind, amount)| {
+            let owned = self.get_resource_count(kind);
+            if owned >= amount {
+                return None
+            }
+            Some(Resource { r#type: kind, value: amount - owned })
+        }).collect()
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
+    // the hand of the player after paying for the structure, None if it cannot be paid for
+    pub fn resources_after(&self, structure: StructureKind) -> Option<Vec<Resource>> {
+        if !self.can_afford(structure) {
+            return None
+        }
+        let cost = structure.get_cost();
+        Some(self.resources.iter().map(|resource| {
+            let paid: usize = cost.iter().filter(|(kind, _)| *kind == resource.r#type).map(|(_, amount)| amount).sum();
+            Resource { r#type: resource.r#type, value: resource.value - paid.min(resource.value) }
+        }).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Resource {
-    pub r#type: String,
+    pub r#type: ResourceKind,
     pub value: usize,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct ServerInputBandit{
     pub model: String,
     pub attributes: Bandit,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Bandit {
     pub tile_key: String
 }
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerInputPort {
+    pub model: String,
+    pub attributes: Port,
+}
+
+// A harbor on a coastal edge, the two nodes of the edge can use it
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Port {
+    pub edge_key: String,
+    #[serde(rename = "type", with = "port_kind")]
+    pub kind: PortKind,
+    // the server may use other ratios than the usual ones
+    #[serde(default)]
+    pub ratio: Option<usize>,
+}
+
+impl Port {
+    // the amount of a resource the port takes for one other resource, None if it does not take
+    // the resource at all
+    pub fn get_ratio(&self, resource: ResourceKind) -> Option<usize> {
+        match self.kind {
+            PortKind::Generic => Some(self.ratio.unwrap_or(GENERIC_PORT_RATIO)),
+            PortKind::Resource(port_resource) if port_resource == resource => Some(self.ratio.unwrap_or(RESOURCE_PORT_RATIO)),
+            PortKind::Resource(_) => None,
+        }
+    }
+}
+
+pub const GENERIC_PORT_RATIO: usize = 3;
+pub const RESOURCE_PORT_RATIO: usize = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PortKind {
+    Generic,
+    Resource(ResourceKind),
+}
+
+// where the ports go when the server does not send them
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PortLayout {
+    // the nine ports of the base game, spread around the coast
+    Standard,
+    None,
+}
+
+const STANDARD_PORTS: [PortKind; 9] = [
+    PortKind::Generic,
+    PortKind::Resource(ResourceKind::Wood),
+    PortKind::Generic,
+    PortKind::Resource(ResourceKind::Wool),
+    PortKind::Generic,
+    PortKind::Resource(ResourceKind::Grain),
+    PortKind::Generic,
+    PortKind::Resource(ResourceKind::Stone),
+    PortKind::Resource(ResourceKind::Ore),
+];
+
+// The resources as the server names them. Stone is what the board game calls brick.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ResourceKind {
+    Wood,
+    Wool,
+    Grain,
+    Stone,
+    Ore,
+}
+
+impl ResourceKind {
+    pub const ALL: [ResourceKind; 5] = [ResourceKind::Wood, ResourceKind::Wool, ResourceKind::Grain, ResourceKind::Stone, ResourceKind::Ore];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TileKind {
+    Wood,
+    Wool,
+    Grain,
+    Stone,
+    Ore,
+    Desert,
+    #[serde(alias = "sea")]
+    Water,
+}
+
+impl TileKind {
+    // the resource a tile produces, deserts and water produce nothing
+    pub fn resource(&self) -> Option<ResourceKind> {
+        match self {
+            TileKind::Wood => Some(ResourceKind::Wood),
+            TileKind::Wool => Some(ResourceKind::Wool),
+            TileKind::Grain => Some(ResourceKind::Grain),
+            TileKind::Stone => Some(ResourceKind::Stone),
+            TileKind::Ore => Some(ResourceKind::Ore),
+            TileKind::Desert | TileKind::Water => None,
+        }
+    }
+
+    pub fn is_land(&self) -> bool {
+        *self != TileKind::Water
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum StructureKind {
+    Street,
+    Village,
+    City,
+}
+
+// the structures worth saving for, the most valuable first
+pub const BUILD_PRIORITY: [StructureKind; 3] = [StructureKind::City, StructureKind::Village, StructureKind::Street];
+
+impl StructureKind {
+    // The resources needed to build a structure
+    pub fn get_cost(&self) -> Vec<(ResourceKind, usize)> {
+        match self {
+            StructureKind::Street => vec!((ResourceKind::Wood, 1), (ResourceKind::Stone, 1)),
+            StructureKind::Village => vec!((ResourceKind::Wood, 1), (ResourceKind::Stone, 1), (ResourceKind::Grain, 1), (ResourceKind::Wool, 1)),
+            StructureKind::City => vec!((ResourceKind::Ore, 3), (ResourceKind::Grain, 2)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DevelopmentCardKind {
+    // moves the bandit, three or more make the largest army
+    Knight,
+    // two free streets
+    RoadBuilding,
+    // two free resources from the bank
+    YearOfPlenty,
+    // all cards of one resource from the other players
+    Monopoly,
+    // a point that counts right away and is never played
+    VictoryPoint,
+}
+
+pub const DEVELOPMENT_CARD_COST: [(ResourceKind, usize); 3] = [(ResourceKind::Ore, 1), (ResourceKind::Wool, 1), (ResourceKind::Grain, 1)];
+
+// The server sends an empty string for nodes without a structure
+mod optional_structure {
+    use super::StructureKind;
+    use serde::de::IntoDeserializer;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(structure: &Option<StructureKind>, serializer: S) -> Result<S::Ok, S::Error> {
+        match structure {
+            Some(structure) => structure.serialize(serializer),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<StructureKind>, D::Error> {
+        let structure: Option<String> = Option::deserialize(deserializer)?;
+        match structure.as_deref() {
+            None | Some("") => Ok(None),
+            Some(structure) => StructureKind::deserialize(IntoDeserializer::<D::Error>::into_deserializer(structure)).map(Some),
+        }
+    }
+}
+
+// The server names a port after the resource it takes, or "generic"
+mod port_kind {
+    use super::{PortKind, ResourceKind};
+    use serde::de::IntoDeserializer;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(kind: &PortKind, serializer: S) -> Result<S::Ok, S::Error> {
+        match kind {
+            PortKind::Generic => serializer.serialize_str("generic"),
+            PortKind::Resource(resource) => resource.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PortKind, D::Error> {
+        let kind = String::deserialize(deserializer)?;
+        match kind.as_str() {
+            "generic" => Ok(PortKind::Generic),
+            resource => ResourceKind::deserialize(IntoDeserializer::<D::Error>::into_deserializer(resource)).map(PortKind::Resource),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // A strip of ten tiles t0 - t9 with a corner n<i> between t<i>, t<i+1> and t<i+2>. The edge
+    // p<i> between t<i> and t<i+1> connects n<i-1> with n<i>, the edge d<i> between t<i> and t<i+2>
+    // only touches n<i>, so the corners form a path n0 - n1 - .. - n7.
+    // Player 0 has a village on n0 and streets from there up to n4, where player 1 has a village
+    // with streets up to n6.
+    pub fn two_player_board() -> Board {
+        let tile_key = |i: usize| format!("t{}", i);
+        let tiles = (0..10).map(|i| Tile { key: tile_key(i), resource_type: TileKind::Grain, number: 6, x: i as u8, y: 0 }).collect();
+        let villages = [(0, 0), (4, 1)];
+        let nodes = (0..8).map(|i| {
+            let player = villages.iter().find(|(node, _)| *node == i).map(|(_, player)| *player);
+            Node {
+                key: format!("n{}", i),
+                structure: player.map(|_| StructureKind::Village),
+                player,
+                t_key: tile_key(i),
+                l_key: tile_key(i + 1),
+                r_key: tile_key(i + 2),
+            }
+        }).collect();
+        let streets = [(1, 0), (2, 0), (3, 0), (4, 0), (5, 1), (6, 1)];
+        let mut edges: Vec<Edge> = (0..9).map(|i| {
+            let player = streets.iter().find(|(edge, _)| *edge == i).map(|(_, player)| *player);
+            Edge { key: format!("({},{})", tile_key(i), tile_key(i + 1)), player, road: player.is_some() }
+        }).collect();
+        edges.extend((0..8).map(|i| Edge { key: format!("({},{})", tile_key(i), tile_key(i + 2)), player: None, road: false }));
+        Board::new(tiles, nodes, edges, Vec::new(), Vec::new())
+    }
+
+    pub fn get_player(id: usize) -> Player {
+        Player {
+            id,
+            color: String::from("red"),
+            name: format!("player {}", id),
+            resources: Vec::new(),
+            development_cards: Vec::new(),
+            played_knights: 0,
+        }
+    }
+
+    // the key of the path edge p<i>
+    pub fn path_edge(i: usize) -> String {
+        format!("(t{},t{})", i, i + 1)
+    }
+
+    // the key of the dangling edge d<i>
+    pub fn dangling_edge(i: usize) -> String {
+        format!("(t{},t{})", i, i + 2)
+    }
+
+    pub fn get_edge_keys(edges: Vec<&Edge>) -> Vec<String> {
+        edges.into_iter().map(|edge| edge.key.clone()).sorted().dedup().collect()
+    }
+
+    pub fn get_node_keys(nodes: Vec<&Node>) -> Vec<String> {
+        nodes.into_iter().map(|node| node.key.clone()).sorted().dedup().collect()
+    }
+
+    pub fn sorted(keys: Vec<String>) -> Vec<String> {
+        keys.into_iter().sorted().collect()
+    }
+
+    #[test]
+    fn edges_from_player_are_only_own_roads() {
+        let board = two_player_board();
+        assert_eq!(get_edge_keys(board.get_edges_from_player(&get_player(0))), sorted((1..=4).map(path_edge).collect()));
+        assert_eq!(get_edge_keys(board.get_edges_from_player(&get_player(1))), sorted((5..=6).map(path_edge).collect()));
+    }
+
+    #[test]
+    fn potential_streets_stop_at_opponent_village() {
+        let board = two_player_board();
+        assert_eq!(get_edge_keys(board.get_potential_street_edges(&get_player(0))),
+            sorted(vec!(path_edge(0), dangling_edge(0), dangling_edge(1), dangling_edge(2), dangling_edge(3))));
+        assert_eq!(get_edge_keys(board.get_potential_street_edges(&get_player(1))),
+            sorted(vec!(dangling_edge(4), dangling_edge(5), dangling_edge(6), path_edge(7))));
+    }
+
+    #[test]
+    fn potential_villages_are_on_own_roads() {
+        let board = two_player_board();
+        assert_eq!(get_node_keys(board.get_potential_village_nodes(&get_player(0))), vec!(String::from("n2")));
+        assert_eq!(get_node_keys(board.get_potential_village_nodes(&get_player(1))), vec!(String::from("n6")));
+    }
+}
@@ -1,18 +1,48 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::board::*;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BuildCommand {
-    pub structure: String,
+    pub structure: StructureKind,
     pub location: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct TradeCommand {
-    pub from: String,
-    pub to: String,
+    pub from: ResourceKind,
+    pub to: ResourceKind,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MoveBanditCommand {
     pub location: String
 }
+
+// Buy one card from the top of the development card deck
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BuyDevelopmentCardCommand {}
+
+// Play a development card, with what the card needs to know. Victory points are never played.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "card", rename_all = "snake_case")]
+pub enum PlayDevelopmentCardCommand {
+    // the tile to move the bandit to
+    Knight { location: String },
+    // the edges to build the free streets on, in order
+    RoadBuilding { streets: Vec<String> },
+    // the two resources to take from the bank
+    YearOfPlenty { resources: Vec<ResourceKind> },
+    Monopoly { resource: ResourceKind },
+}
+
+impl PlayDevelopmentCardCommand {
+    pub fn get_card(&self) -> DevelopmentCardKind {
+        match self {
+            PlayDevelopmentCardCommand::Knight { .. } => DevelopmentCardKind::Knight,
+            PlayDevelopmentCardCommand::RoadBuilding { .. } => DevelopmentCardKind::RoadBuilding,
+            PlayDevelopmentCardCommand::YearOfPlenty { .. } => DevelopmentCardKind::YearOfPlenty,
+            PlayDevelopmentCardCommand::Monopoly { .. } => DevelopmentCardKind::Monopoly,
+        }
+    }
+}
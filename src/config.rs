@@ -0,0 +1,190 @@
+use log::LevelFilter;
+use crate::board::PortLayout;
+use std::env;
+use std::time::Duration;
+
+// Settings of the client. Every option can be given on the command line or through an
+// environment variable, the command line wins when both are set.
+
+pub const USAGE: &str = "Usage:
+  catan-client [options]                        connect to a catan-server
+  catan-client [options] local [games] [players] let bots play against each other
+  catan-client [options] bench [iterations]     compare the speed of the board queries
+
+Options:
+  --host <host>         CATAN_HOST       server to connect to (default: localhost)
+  --port <port>         CATAN_PORT       port of the server (default: 10006)
+  --name <name>         CATAN_NAME       name shown in the game (default: Luke Skywalker)
+  --strategy <name>     CATAN_STRATEGY   bot strategy: random or heuristic, local games take a comma
+                                         separated list that is handed out to the players (default: random)
+  --seed <number>       CATAN_SEED       seed for the random number generators (default: random)
+  --log-level <level>   CATAN_LOG_LEVEL  off, error, warn, info, debug or trace (default: info)
+  --reconnect <policy>  CATAN_RECONNECT  never, always or a maximum number of attempts (default: always)
+  --ports <layout>      CATAN_PORTS      ports to assume when the server sends none: standard or none
+                                         (default: standard)
+  --help                                 show this message";
+
+const OPTIONS: [&str; 8] = ["--host", "--port", "--name", "--strategy", "--seed", "--log-level", "--reconnect", "--ports"];
+
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Debug, PartialEq)]
+pub enum Mode {
+    Connect,
+    Local { games: u32, players: usize },
+    Bench { iterations: u32 },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ReconnectPolicy {
+    Never,
+    Always,
+    Attempts(u32),
+}
+
+impl ReconnectPolicy {
+    // whether we may connect again after the given number of failed attempts in a row
+    pub fn allows(&self, failed_attempts: u32) -> bool {
+        match self {
+            ReconnectPolicy::Never => failed_attempts == 0,
+            ReconnectPolicy::Always => true,
+            ReconnectPolicy::Attempts(max) => failed_attempts < *max,
+        }
+    }
+
+    // how long to wait before the next attempt, doubles with every failed attempt in a row
+    pub fn get_delay(&self, failed_attempts: u32) -> Duration {
+        let delay = INITIAL_RECONNECT_DELAY * 2u32.saturating_pow(failed_attempts.saturating_sub(1));
+        delay.min(MAX_RECONNECT_DELAY)
+    }
+}
+
+#[derive(Debug)]
+pub struct Config {
+    pub mode: Mode,
+    pub host: String,
+    pub port: u16,
+    pub name: String,
+    pub strategy: String,
+    pub seed: Option<u64>,
+    pub log_level: LevelFilter,
+    pub reconnect: ReconnectPolicy,
+    pub ports: PortLayout,
+    pub show_help: bool,
+}
+
+impl Config {
+    // Read the config from the environment and the given command line arguments (without the
+    // name of the program)
+    pub fn from_args(args: &[String]) -> Result<Config, String> {
+        let mut config = Config {
+            mode: Mode::Connect,
+            host: env::var("CATAN_HOST").unwrap_or_else(|_| String::from("localhost")),
+            port: parse_env("CATAN_PORT", parse_port)?.unwrap_or(10006),
+            name: env::var("CATAN_NAME").unwrap_or_else(|_| String::from("Luke Skywalker")),
+            strategy: env::var("CATAN_STRATEGY").unwrap_or_else(|_| String::from("random")),
+            seed: parse_env("CATAN_SEED", parse_seed)?,
+            log_level: parse_env("CATAN_LOG_LEVEL", parse_log_level)?.unwrap_or(LevelFilter::Info),
+            reconnect: parse_env("CATAN_RECONNECT", parse_reconnect)?.unwrap_or(ReconnectPolicy::Always),
+            ports: parse_env("CATAN_PORTS", parse_port_layout)?.unwrap_or(PortLayout::Standard),
+            show_help: false,
+        };
+
+        let mut positional: Vec<&str> = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            // options can be written as `--port 10006` or `--port=10006`
+            let (option, inline_value) = match arg.find('=') {
+                Some(index) if arg.starts_with("--") => (&arg[..index], Some(&arg[index + 1..])),
+                _ => (arg.as_str(), None),
+            };
+            if option == "--help" || option == "-h" {
+                config.show_help = true;
+                continue
+            }
+            if !option.starts_with("--") {
+                positional.push(option);
+                continue
+            }
+            if !OPTIONS.contains(&option) {
+                return Err(format!("unknown option {}", option))
+            }
+
+            let value = match inline_value {
+                Some(value) => value,
+                None => args.next().map(String::as_str).ok_or(format!("missing value for {}", option))?,
+            };
+            match option {
+                "--host" => config.host = String::from(value),
+                "--port" => config.port = parse_port(value)?,
+                "--name" => config.name = String::from(value),
+                "--strategy" => config.strategy = String::from(value),
+                "--seed" => config.seed = Some(parse_seed(value)?),
+                "--log-level" => config.log_level = parse_log_level(value)?,
+                "--reconnect" => config.reconnect = parse_reconnect(value)?,
+                "--ports" => config.ports = parse_port_layout(value)?,
+                _ => unreachable!(),
+            }
+        }
+
+        config.mode = match positional.as_slice() {
+            [] => Mode::Connect,
+            ["local", rest @ ..] if rest.len() <= 2 => Mode::Local {
+                games: rest.first().map(|games| parse_number(games, "number of games")).transpose()?.unwrap_or(1),
+                players: rest.get(1).map(|players| parse_number(players, "number of players")).transpose()?.unwrap_or(4),
+            },
+            ["bench"] => Mode::Bench { iterations: 1000 },
+            ["bench", iterations] => Mode::Bench { iterations: parse_iterations(iterations)? },
+            _ => return Err(format!("unexpected arguments: {}", positional.join(" "))),
+        };
+        Ok(config)
+    }
+}
+
+fn parse_env<T>(name: &str, parse: fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    match env::var(name) {
+        Ok(value) => parse(&value).map(Some).map_err(|e| format!("{} in {}", e, name)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid {}: {}", what, value))
+}
+
+fn parse_port(value: &str) -> Result<u16, String> {
+    parse_number(value, "port")
+}
+
+fn parse_seed(value: &str) -> Result<u64, String> {
+    parse_number(value, "seed")
+}
+
+// the benchmark divides the time by the iterations, so it needs at least one
+fn parse_iterations(value: &str) -> Result<u32, String> {
+    match parse_number(value, "number of iterations")? {
+        0 => Err(format!("invalid number of iterations: {}, at least 1 is needed", value)),
+        iterations => Ok(iterations),
+    }
+}
+
+fn parse_log_level(value: &str) -> Result<LevelFilter, String> {
+    value.parse().map_err(|_| format!("invalid log level: {}", value))
+}
+
+fn parse_reconnect(value: &str) -> Result<ReconnectPolicy, String> {
+    match value {
+        "never" => Ok(ReconnectPolicy::Never),
+        "always" => Ok(ReconnectPolicy::Always),
+        attempts => parse_number(attempts, "reconnect policy").map(ReconnectPolicy::Attempts),
+    }
+}
+
+fn parse_port_layout(value: &str) -> Result<PortLayout, String> {
+    match value {
+        "standard" => Ok(PortLayout::Standard),
+        "none" => Ok(PortLayout::None),
+        _ => Err(format!("invalid port layout: {}", value)),
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use crate::board::*;
+
+// Plans the answer to a ForceDiscardRequest: half of the hand, rounded down, has to go. We keep
+// the cards for the structure we are closest to building and throw away what we have most of.
+
+// the number of cards that has to be discarded
+pub fn get_discard_count(player: &Player) -> usize {
+    player.get_resource_total() / 2
+}
+
+pub fn plan_discard(player: &Player) -> Vec<Resource> {
+    let mut remaining: HashMap<ResourceKind, usize> = ResourceKind::ALL.iter().map(|resource| (*resource, player.get_resource_count(*resource))).collect();
+    let count = get_discard_count(player);
+    let mut keep = player.get_resource_total() - count;
+
+    // the build that is missing the fewest cards goes first, the others only if there is room left
+    let mut builds = BUILD_PRIORITY.to_vec();
+    builds.sort_by_key(|structure| player.missing_for(*structure).iter().map(|resource| resource.value).sum::<usize>());
+    let mut reserved: HashMap<ResourceKind, usize> = HashMap::new();
+    for structure in builds {
+        let needed: Vec<(ResourceKind, usize)> = structure.get_cost().into_iter().map(|(resource, amount)| {
+            let available = remaining[&resource] - reserved.get(&resource).cloned().unwrap_or(0);
+            (resource, amount.min(available))
+        }).collect();
+        let total: usize = needed.iter().map(|(_, amount)| amount).sum();
+        if total > keep {
+            continue
+        }
+        keep -= total;
+        for (resource, amount) in needed {
+            *reserved.entry(resource).or_insert(0) += amount;
+        }
+    }
+
+    // discard one card at a time from the resource with the most cards that are not reserved,
+    // the reservations never add up to more than we keep so there is always one
+    let mut discarded: HashMap<ResourceKind, usize> = HashMap::new();
+    for _ in 0..count {
+        let unreserved = |resource: &ResourceKind| remaining[resource] - reserved.get(resource).cloned().unwrap_or(0);
+        let resource = match ResourceKind::ALL.iter().cloned().filter(|resource| unreserved(resource) > 0).max_by_key(unreserved) {
+            Some(resource) => resource,
+            None => break,
+        };
+        *remaining.get_mut(&resource).unwrap() -= 1;
+        *discarded.entry(resource).or_insert(0) += 1;
+    }
+
+    ResourceKind::ALL.iter()
+        .filter_map(|resource| discarded.get(resource).map(|value| Resource { r#type: *resource, value: *value }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::get_player;
+
+    fn get_hand(cards: &[(ResourceKind, usize)]) -> Player {
+        let mut player = get_player(0);
+        player.resources = ResourceKind::ALL.iter().map(|resource| {
+            let value = cards.iter().filter(|(kind, _)| kind == resource).map(|(_, amount)| amount).sum();
+            Resource { r#type: *resource, value }
+        }).collect();
+        player
+    }
+
+    // discards exactly the required number of cards, and only cards the player has
+    fn check_discard(player: &Player) -> Vec<Resource> {
+        let discard = plan_discard(player);
+        assert_eq!(discard.iter().map(|resource| resource.value).sum::<usize>(), get_discard_count(player));
+        for resource in &discard {
+            assert!(resource.value <= player.get_resource_count(resource.r#type), "discarding {:?} of {:?}", resource, player.resources);
+        }
+        discard
+    }
+
+    fn get_discarded(discard: &[Resource], kind: ResourceKind) -> usize {
+        discard.iter().filter(|resource| resource.r#type == kind).map(|resource| resource.value).sum()
+    }
+
+    #[test]
+    fn discards_half_of_eight_cards() {
+        let player = get_hand(&[(ResourceKind::Wood, 2), (ResourceKind::Stone, 2), (ResourceKind::Grain, 1), (ResourceKind::Wool, 1), (ResourceKind::Ore, 2)]);
+        assert_eq!(get_discard_count(&player), 4);
+        check_discard(&player);
+    }
+
+    #[test]
+    fn discards_from_a_single_resource() {
+        let player = get_hand(&[(ResourceKind::Wood, 9)]);
+        assert_eq!(get_discard_count(&player), 4);
+        assert_eq!(get_discarded(&check_discard(&player), ResourceKind::Wood), 4);
+    }
+
+    #[test]
+    fn rounds_down_for_seven_cards() {
+        let player = get_hand(&[(ResourceKind::Wood, 3), (ResourceKind::Wool, 2), (ResourceKind::Ore, 2)]);
+        assert_eq!(get_discard_count(&player), 3);
+        check_discard(&player);
+    }
+
+    #[test]
+    fn keeps_the_cards_for_a_city() {
+        let player = get_hand(&[(ResourceKind::Ore, 3), (ResourceKind::Grain, 2), (ResourceKind::Wool, 3), (ResourceKind::Wood, 1)]);
+        let discard = check_discard(&player);
+        assert_eq!(get_discarded(&discard, ResourceKind::Ore), 0);
+        assert_eq!(get_discarded(&discard, ResourceKind::Grain), 0);
+    }
+}
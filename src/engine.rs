@@ -0,0 +1,918 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use log::{info, warn};
+use rand::{Rng, SeedableRng};
+
+use crate::board::*;
+use crate::commands::*;
+use crate::game::*;
+use crate::graph::*;
+use crate::server::*;
+use crate::scoring::*;
+use crate::strategy::*;
+use crate::trading::*;
+
+// An in-process version of the rules of the catan-server. It creates a board in the same format
+// as the server, asks the strategies for their moves with the same requests (codes 100-104) and
+// applies their commands when they are legal. It can be used to let bots play against each other
+// without a network connection.
+
+const COLORS: [&str; 6] = ["red", "blue", "white", "orange", "green", "brown"];
+const MAX_ATTEMPTS: usize = 10;
+const BOARD_RADIUS: i8 = 2;
+// the cards in the development card deck of the base game
+const DEVELOPMENT_DECK: [(DevelopmentCardKind, usize); 5] = [
+    (DevelopmentCardKind::Knight, 14),
+    (DevelopmentCardKind::RoadBuilding, 2),
+    (DevelopmentCardKind::YearOfPlenty, 2),
+    (DevelopmentCardKind::Monopoly, 2),
+    (DevelopmentCardKind::VictoryPoint, 5),
+];
+const ROAD_BUILDING_STREETS: usize = 2;
+const YEAR_OF_PLENTY_RESOURCES: usize = 2;
+// the number of events in a snapshot, the server also only sends the last part of the log
+const EVENTS_IN_SNAPSHOT: usize = 100;
+
+// the three directions to neighbouring tiles in axial coordinates, the other three are the opposites
+const DIRECTIONS: [(i8, i8); 3] = [(1, 0), (0, 1), (-1, 1)];
+
+pub struct GameResult {
+    pub winner: Option<usize>,
+    pub turns: u32,
+    pub points: Vec<u32>,
+}
+
+pub struct LocalGame {
+    strategies: Vec<Box<dyn Strategy>>,
+    players: Vec<Player>,
+    tiles: Vec<Tile>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    ports: Vec<Port>,
+    bandit: String,
+    development_deck: Vec<DevelopmentCardKind>,
+    rng: StdRng,
+    move_count: u32,
+    events: Vec<Event>,
+    last_dice_throw: Option<u8>,
+    phase: GamePhase,
+    current_player: usize,
+    // the longest road and largest army stay with their holder on a tie
+    holders: Holders,
+}
+
+impl LocalGame {
+    pub fn new(strategies: Vec<Box<dyn Strategy>>, seed: u64) -> LocalGame {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (tiles, nodes, edges) = create_board(&mut rng);
+        let mut board = Board::new(tiles.clone(), nodes.clone(), edges.clone(), Vec::new(), Vec::new());
+        board.add_default_ports(PortLayout::Standard);
+        let ports = board.get_ports().into_iter().cloned().collect();
+        let bandit = tiles.iter().find(|tile| tile.resource_type == TileKind::Desert).map(|tile| tile.key.clone()).unwrap_or_default();
+        let players = (0..strategies.len()).map(|id| {
+            Player {
+                id,
+                color: String::from(COLORS[id % COLORS.len()]),
+                name: format!("Bot {}", id),
+                resources: ResourceKind::ALL.iter().map(|resource| Resource { r#type: *resource, value: 0 }).collect(),
+                development_cards: Vec::new(),
+                played_knights: 0,
+            }
+        }).collect();
+        let mut development_deck: Vec<DevelopmentCardKind> = DEVELOPMENT_DECK.iter()
+            .flat_map(|(card, amount)| std::iter::repeat_n(*card, *amount))
+            .collect();
+        development_deck.shuffle(&mut rng);
+
+        LocalGame {
+            strategies,
+            players,
+            tiles,
+            nodes,
+            edges,
+            ports,
+            bandit,
+            development_deck,
+            rng,
+            move_count: 0,
+            events: Vec::new(),
+            last_dice_throw: None,
+            phase: GamePhase::InitialBuild,
+            current_player: 0,
+            holders: Holders::default(),
+        }
+    }
+
+    // Play the game until someone wins or max_turns rounds have been played
+    pub fn play(mut self, max_turns: u32) -> GameResult {
+        self.place_initial_buildings();
+
+        for turn in 1..=max_turns {
+            if let Some(winner) = self.play_round() {
+                return self.result(Some(winner), turn)
+            }
+        }
+        self.result(None, max_turns)
+    }
+
+    // Let every player play one turn, returns the winner if there is one
+    pub fn play_round(&mut self) -> Option<usize> {
+        for player in 0..self.players.len() {
+            self.current_player = player;
+            self.play_turn(player);
+            if self.get_points()[player] >= WINNING_POINTS {
+                return Some(player)
+            }
+        }
+        None
+    }
+
+    // Let every player place its two initial villages and streets
+    pub fn place_initial_buildings(&mut self) {
+        let player_count = self.players.len();
+
+        // snake order: 0, 1, .., n, n, .., 1, 0
+        for player in (0..player_count).chain((0..player_count).rev()) {
+            self.current_player = player;
+            let is_second_placement = !self.get_nodes_from_player(player).is_empty();
+            self.request_initial_build(player, is_second_placement);
+        }
+        self.phase = GamePhase::ThrowDice;
+    }
+
+    fn result(&mut self, winner: Option<usize>, turns: u32) -> GameResult {
+        GameResult {
+            winner,
+            turns,
+            points: self.get_points(),
+        }
+    }
+
+    fn play_turn(&mut self, player: usize) {
+        self.phase = GamePhase::ThrowDice;
+        self.request_play_development_card(player);
+        let dice_throw = self.rng.gen_range(1, 7) + self.rng.gen_range(1, 7);
+        self.last_dice_throw = Some(dice_throw);
+        self.log_event("throw_dice", player, format!("Player {} threw {}", player, dice_throw), Vec::new(), Vec::new());
+
+        if dice_throw == 7 {
+            self.phase = GamePhase::ForceDiscard;
+            for other in 0..self.players.len() {
+                if self.players[other].get_resource_total() > 7 {
+                    self.request_force_discard(other);
+                }
+            }
+            self.phase = GamePhase::MoveBandit;
+            self.request_move_bandit(player);
+        } else {
+            self.produce(dice_throw);
+        }
+
+        self.phase = GamePhase::Trade;
+        self.request_trade(player);
+        self.phase = GamePhase::Build;
+        self.request_build(player);
+        self.request_buy_development_cards(player);
+    }
+
+    // The view of the game for a single player, as the server would send it.
+    pub fn snapshot(&self, player: usize) -> Game {
+        // the resources and development cards of the others are secret
+        let mut players = self.players.clone();
+        for other in players.iter_mut().filter(|other| other.id != player) {
+            other.resources.clear();
+            other.development_cards.clear();
+        }
+        let mut game = Game::new(players, self.board());
+        game.move_count = Some(self.move_count);
+        let first_event = self.events.len().saturating_sub(EVENTS_IN_SNAPSHOT);
+        game.events = Some(self.events[first_event..].iter().map(|event| {
+            ServerInputEvent { model: String::from("event"), attributes: serde_json::to_value(event).unwrap_or_default() }
+        }).collect());
+        game.last_dice_throw = self.last_dice_throw;
+        game.phase = Some(self.phase);
+        game.current_player = Some(self.current_player as u8);
+        game.set_my_id(player as i16);
+        game
+    }
+
+    // Show the player the game before asking it something, like the server does. The graph over
+    // its board is built once and handed to every decision about the snapshot.
+    fn send_snapshot(&mut self, player: usize) -> (Game, BoardGraph) {
+        let game = self.snapshot(player);
+        let graph = BoardGraph::new(self.board());
+        self.strategies[player].observe(&game, &graph);
+        (game, graph)
+    }
+
+    fn board(&self) -> Board {
+        let bandit = Bandit { tile_key: self.bandit.clone() };
+        Board::new(self.tiles.clone(), self.nodes.clone(), self.edges.clone(), vec!(bandit), self.ports.clone())
+    }
+
+    // Every event is a move of its own, so the history of the strategies can tell them apart
+    fn log_event(&mut self, event_type: &str, player: usize, message: String, resources: Vec<ResourceKind>, structures: Vec<StructureKind>) {
+        self.move_count += 1;
+        self.events.push(Event {
+            event_type: String::from(event_type),
+            move_count: Some(self.move_count),
+            message,
+            player,
+            resources,
+            structures,
+            development_card: None,
+            monopoly: None,
+        });
+    }
+
+    fn log_development_card(&mut self, player: usize, card: DevelopmentCardKind, message: String, resources: Vec<ResourceKind>, structures: Vec<StructureKind>) {
+        self.log_event("play_development_card", player, message, resources, structures);
+        if let Some(event) = self.events.last_mut() {
+            event.development_card = Some(card);
+        }
+    }
+
+    // Tell the strategy of a player that its answer was not accepted, like the server would
+    fn reject(&mut self, player: usize, request: ResponseCode, description: &str, additional_info: &str) {
+        let game = self.snapshot(player);
+        let rejection = Rejection {
+            request,
+            description: String::from(description),
+            additional_info: String::from(additional_info),
+        };
+        self.strategies[player].rejected(&game, &rejection);
+    }
+
+    // InitialBuildRequest (102): place a village and a street next to it
+    fn request_initial_build(&mut self, player: usize, is_second_placement: bool) {
+        for _ in 0..MAX_ATTEMPTS {
+            let (game, graph) = self.send_snapshot(player);
+            let commands = match self.strategies[player].initial_build(&game, &graph) {
+                Ok(commands) => commands,
+                Err(e) => {
+                    warn!("Player {} could not respond to {:?}: {}", player, ResponseCode::InitialBuildRequest, e);
+                    continue
+                }
+            };
+            let village = commands.iter().find(|command| command.structure == StructureKind::Village);
+            let street = commands.iter().find(|command| command.structure == StructureKind::Street);
+            if let (Some(village), Some(street)) = (village, street) {
+                let graph = BoardGraph::new(self.board());
+                if is_valid_initial_build(&graph, &village.location, &street.location) {
+                    self.place_village(player, &village.location);
+                    self.place_street(player, &street.location);
+                    self.log_event("build", player, format!("Player {} placed a village and a street", player),
+                        Vec::new(), vec!(StructureKind::Village, StructureKind::Street));
+                    if is_second_placement {
+                        self.hand_out_initial_resources(&graph, player, &village.location);
+                    }
+                    return
+                }
+                self.reject(player, ResponseCode::InitialBuildRequest, "a structure cannot be built there", &village.location);
+            } else {
+                self.reject(player, ResponseCode::InitialBuildRequest, "a village and a street next to it are needed", "");
+            }
+        }
+        warn!("Player {} did not make a valid initial placement", player);
+    }
+
+    // ForceDiscardRequest (104): discard half of the hand, rounded down
+    fn request_force_discard(&mut self, player: usize) {
+        let required = self.players[player].get_resource_total() / 2;
+        for _ in 0..MAX_ATTEMPTS {
+            let (game, graph) = self.send_snapshot(player);
+            let discards = match self.strategies[player].discard(&game, &graph) {
+                Ok(discards) => discards,
+                Err(e) => {
+                    warn!("Player {} could not respond to {:?}: {}", player, ResponseCode::ForceDiscardRequest, e);
+                    continue
+                }
+            };
+            let total: usize = discards.iter().map(|resource| resource.value).sum();
+            let affordable = discards.iter().all(|resource| {
+                self.players[player].get_resource_count(resource.r#type) >= resource.value
+            });
+            if total == required && affordable {
+                self.discard(player, discards);
+                return
+            }
+            self.reject(player, ResponseCode::ForceDiscardRequest, "the discarded resources do not add up to half of the hand", "");
+        }
+
+        // the player keeps sending invalid discards, throw away random cards instead
+        let mut hand = self.players[player].clone();
+        let mut discards = Vec::new();
+        for _ in 0..required {
+            let owned: Vec<ResourceKind> = ResourceKind::ALL.iter().cloned().filter(|resource| {
+                hand.get_resource_count(*resource) > 0
+            }).collect();
+            if let Some(resource) = owned.choose(&mut self.rng) {
+                take_resource(&mut hand, *resource, 1);
+                discards.push(Resource { r#type: *resource, value: 1 });
+            }
+        }
+        self.discard(player, discards);
+    }
+
+    fn discard(&mut self, player: usize, discards: Vec<Resource>) {
+        let mut discarded = Vec::new();
+        for resource in discards {
+            take_resource(&mut self.players[player], resource.r#type, resource.value);
+            discarded.extend(std::iter::repeat_n(resource.r#type, resource.value));
+        }
+        self.log_event("force_discard", player, format!("Player {} discarded {} resources", player, discarded.len()), discarded, Vec::new());
+    }
+
+    // MoveBanditRequest (103): move the bandit to another land tile and steal from a neighbour
+    fn request_move_bandit(&mut self, player: usize) {
+        let mut location = None;
+        for _ in 0..MAX_ATTEMPTS {
+            let (game, graph) = self.send_snapshot(player);
+            let commands = match self.strategies[player].move_bandit(&game, &graph) {
+                Ok(commands) => commands,
+                Err(e) => {
+                    warn!("Player {} could not respond to {:?}: {}", player, ResponseCode::MoveBanditRequest, e);
+                    continue
+                }
+            };
+            if let Some(command) = commands.first() {
+                if self.is_valid_bandit_location(&command.location) {
+                    location = Some(command.location.clone());
+                    break
+                }
+                self.reject(player, ResponseCode::MoveBanditRequest, "the bandit cannot be moved there", &command.location);
+            }
+        }
+
+        let location = match location {
+            Some(location) => location,
+            None => {
+                let options: Vec<String> = self.tiles.iter()
+                    .filter(|tile| self.is_valid_bandit_location(&tile.key))
+                    .map(|tile| tile.key.clone())
+                    .collect();
+                options.choose(&mut self.rng).cloned().unwrap_or_else(|| self.bandit.clone())
+            }
+        };
+        self.bandit = location;
+        self.log_event("move_bandit", player, format!("Player {} moved the bandit to {}", player, self.bandit), Vec::new(), Vec::new());
+        self.steal(player);
+    }
+
+    // steal a random card from a random opponent next to the bandit
+    fn steal(&mut self, player: usize) {
+        let graph = BoardGraph::new(self.board());
+        let victims: Vec<usize> = graph.get_tile_by_key(&self.bandit).into_iter()
+            .flat_map(|tile| graph.get_nodes_surrounding_tile(tile))
+            .filter_map(|node| node.player)
+            .filter(|victim| *victim != player && self.players[*victim].get_resource_total() > 0)
+            .collect();
+        if let Some(victim) = victims.choose(&mut self.rng).cloned() {
+            let owned: Vec<ResourceKind> = ResourceKind::ALL.iter().cloned().filter(|resource| {
+                self.players[victim].get_resource_count(*resource) > 0
+            }).collect();
+            if let Some(resource) = owned.choose(&mut self.rng) {
+                take_resource(&mut self.players[victim], *resource, 1);
+                give_resource(&mut self.players[player], *resource, 1);
+                self.log_event("steal", player, format!("Player {} stole a resource from player {}", player, victim), Vec::new(), Vec::new());
+            }
+        }
+    }
+
+    // Development cards are played at the start of a turn. They are bought at the end of a turn,
+    // so every card in the hand was bought before this turn and may be played.
+    fn request_play_development_card(&mut self, player: usize) {
+        let (game, graph) = self.send_snapshot(player);
+        let command = match self.strategies[player].play_development_card(&game, &graph) {
+            Ok(Some(command)) => command,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Player {} could not decide on a development card: {}", player, e);
+                return
+            }
+        };
+        let card = command.get_card();
+        let index = match self.players[player].development_cards.iter().position(|owned| *owned == card) {
+            Some(index) => index,
+            None => {
+                warn!("Player {} tried to play a {:?} it does not have", player, card);
+                return
+            }
+        };
+
+        let message = format!("Player {} played a {:?}", player, card);
+        match command {
+            PlayDevelopmentCardCommand::Knight { location } => {
+                if !self.is_valid_bandit_location(&location) {
+                    self.reject(player, ResponseCode::MoveBanditRequest, "the bandit cannot be moved there", &location);
+                    return
+                }
+                self.players[player].development_cards.remove(index);
+                self.players[player].played_knights += 1;
+                self.log_development_card(player, card, message, Vec::new(), Vec::new());
+                self.bandit = location;
+                self.log_event("move_bandit", player, format!("Player {} moved the bandit to {}", player, self.bandit), Vec::new(), Vec::new());
+                self.steal(player);
+            },
+            PlayDevelopmentCardCommand::RoadBuilding { streets } => {
+                self.players[player].development_cards.remove(index);
+                let mut built = Vec::new();
+                for street in streets.iter().take(ROAD_BUILDING_STREETS) {
+                    // the second street may connect to the first, so it is checked on the board with the first on it
+                    if is_valid_street(&BoardGraph::new(self.board()), player, street) {
+                        self.place_street(player, street);
+                        built.push(StructureKind::Street);
+                    } else {
+                        self.reject(player, ResponseCode::BuildRequest, "a structure cannot be built there", street);
+                    }
+                }
+                self.log_development_card(player, card, message, Vec::new(), built);
+            },
+            PlayDevelopmentCardCommand::YearOfPlenty { resources } => {
+                self.players[player].development_cards.remove(index);
+                let resources: Vec<ResourceKind> = resources.into_iter().take(YEAR_OF_PLENTY_RESOURCES).collect();
+                for resource in &resources {
+                    give_resource(&mut self.players[player], *resource, 1);
+                }
+                self.log_development_card(player, card, message, resources, Vec::new());
+            },
+            PlayDevelopmentCardCommand::Monopoly { resource } => {
+                self.players[player].development_cards.remove(index);
+                let mut taken = 0;
+                for other in (0..self.players.len()).filter(|other| *other != player) {
+                    let amount = self.players[other].get_resource_count(resource);
+                    take_resource(&mut self.players[other], resource, amount);
+                    taken += amount;
+                }
+                give_resource(&mut self.players[player], resource, taken);
+                self.log_development_card(player, card, message, vec!(resource; taken), Vec::new());
+                if let Some(event) = self.events.last_mut() {
+                    event.monopoly = Some(resource);
+                }
+            },
+        }
+    }
+
+    // After building the player can buy development cards, the other players do not get to see
+    // which card it drew
+    fn request_buy_development_cards(&mut self, player: usize) {
+        let (game, graph) = self.send_snapshot(player);
+        let commands = match self.strategies[player].buy_development_cards(&game, &graph) {
+            Ok(commands) => commands,
+            Err(e) => {
+                warn!("Player {} could not decide on buying development cards: {}", player, e);
+                return
+            }
+        };
+        for _ in commands {
+            if !self.players[player].can_afford_development_card() {
+                self.reject(player, ResponseCode::BuildRequest, "the player cannot pay for it", "");
+                continue
+            }
+            let card = match self.development_deck.pop() {
+                Some(card) => card,
+                None => break,
+            };
+            for (resource, amount) in DEVELOPMENT_CARD_COST.iter() {
+                take_resource(&mut self.players[player], *resource, *amount);
+            }
+            self.players[player].development_cards.push(card);
+            self.log_event("buy_development_card", player, format!("Player {} bought a development card", player), Vec::new(), Vec::new());
+        }
+    }
+
+    // TradeRequest (100): trade with the bank or a port of the player
+    fn request_trade(&mut self, player: usize) {
+        let (game, graph) = self.send_snapshot(player);
+        let commands = match self.strategies[player].trade(&game, &graph) {
+            Ok(commands) => commands,
+            Err(e) => {
+                warn!("Player {} could not respond to {:?}: {}", player, ResponseCode::TradeRequest, e);
+                return
+            }
+        };
+        let rates = TradeRates::for_player(&self.board(), &self.players[player]);
+        for command in commands {
+            let ratio = rates.get_ratio(command.from);
+            if self.players[player].get_resource_count(command.from) >= ratio {
+                take_resource(&mut self.players[player], command.from, ratio);
+                give_resource(&mut self.players[player], command.to, 1);
+                let mut resources = vec!(command.from; ratio);
+                resources.push(command.to);
+                self.log_event("trade", player, format!("Player {} traded {} {:?} for 1 {:?}", player, ratio, command.from, command.to), resources, Vec::new());
+            } else {
+                self.reject(player, ResponseCode::TradeRequest, "the player cannot pay for it", "");
+            }
+        }
+    }
+
+    // BuildRequest (101): build streets, villages and cities that are legal and affordable
+    fn request_build(&mut self, player: usize) {
+        let (game, graph) = self.send_snapshot(player);
+        let commands = match self.strategies[player].build(&game, &graph) {
+            Ok(commands) => commands,
+            Err(e) => {
+                warn!("Player {} could not respond to {:?}: {}", player, ResponseCode::BuildRequest, e);
+                return
+            }
+        };
+        for command in commands {
+            let resources = match self.players[player].resources_after(command.structure) {
+                Some(resources) => resources,
+                None => {
+                    self.reject(player, ResponseCode::BuildRequest, "the player cannot pay for it", "");
+                    continue
+                }
+            };
+
+            // every build is checked on the board with the builds before it
+            let graph = BoardGraph::new(self.board());
+            let is_valid = match command.structure {
+                StructureKind::Street => is_valid_street(&graph, player, &command.location),
+                StructureKind::Village => is_valid_village(&graph, player, &command.location),
+                StructureKind::City => self.is_valid_city(player, &command.location),
+            };
+            if !is_valid {
+                self.reject(player, ResponseCode::BuildRequest, "a structure cannot be built there", &command.location);
+                continue
+            }
+
+            self.players[player].resources = resources;
+            match command.structure {
+                StructureKind::Street => self.place_street(player, &command.location),
+                StructureKind::Village => self.place_village(player, &command.location),
+                StructureKind::City => self.place_city(&command.location),
+            }
+            self.log_event("build", player, format!("Player {} built a {:?} at {}", player, command.structure, command.location),
+                Vec::new(), vec!(command.structure));
+        }
+    }
+
+    // give resources to all players with a building next to a tile with the thrown number
+    fn produce(&mut self, dice_throw: u8) {
+        let graph = BoardGraph::new(self.board());
+        let producing_tiles = graph.get_tiles().into_iter().filter(|tile| {
+            tile.number == dice_throw as u32 && tile.key != self.bandit
+        });
+
+        let mut production: Vec<(usize, ResourceKind, usize)> = Vec::new();
+        for tile in producing_tiles {
+            for node in graph.get_nodes_surrounding_tile(tile) {
+                if let (Some(owner), Some(resource)) = (node.player, tile.resource_type.resource()) {
+                    let amount = if node.structure == Some(StructureKind::City) { 2 } else { 1 };
+                    production.push((owner, resource, amount));
+                }
+            }
+        }
+        for (owner, resource, amount) in production {
+            give_resource(&mut self.players[owner], resource, amount);
+        }
+    }
+
+    // after the second initial village the player gets one of each surrounding resource
+    fn hand_out_initial_resources(&mut self, graph: &BoardGraph, player: usize, node_key: &str) {
+        let resources: Vec<ResourceKind> = match graph.get_node_by_key(node_key) {
+            Some(node) => graph.get_tiles_surrounding_node(node).into_iter().filter_map(|tile| tile.resource_type.resource()).collect(),
+            None => return,
+        };
+        for resource in resources {
+            give_resource(&mut self.players[player], resource, 1);
+        }
+    }
+
+    fn is_valid_city(&self, player: usize, node_key: &str) -> bool {
+        self.nodes.iter().any(|node| {
+            node.key == node_key && node.player == Some(player) && node.structure == Some(StructureKind::Village)
+        })
+    }
+
+    fn is_valid_bandit_location(&self, tile_key: &str) -> bool {
+        tile_key != self.bandit && self.tiles.iter().any(|tile| {
+            tile.key == tile_key && tile.resource_type.is_land()
+        })
+    }
+
+    fn place_village(&mut self, player: usize, node_key: &str) {
+        if let Some(node) = self.nodes.iter_mut().find(|node| node.key == node_key) {
+            node.player = Some(player);
+            node.structure = Some(StructureKind::Village);
+        }
+    }
+
+    fn place_city(&mut self, node_key: &str) {
+        if let Some(node) = self.nodes.iter_mut().find(|node| node.key == node_key) {
+            node.structure = Some(StructureKind::City);
+        }
+    }
+
+    fn place_street(&mut self, player: usize, edge_key: &str) {
+        if let Some(edge) = self.edges.iter_mut().find(|edge| edge.key == edge_key) {
+            edge.player = Some(player);
+            edge.road = true;
+        }
+    }
+
+    fn get_nodes_from_player(&self, player: usize) -> Vec<&Node> {
+        self.nodes.iter().filter(|node| node.player == Some(player)).collect()
+    }
+
+    // The points of every player, after handing the longest road and largest army to whoever
+    // has more than their holder now
+    fn get_points(&mut self) -> Vec<u32> {
+        let graph = BoardGraph::new(self.board());
+        let scoreboard = Scoreboard::new(&graph, self.players.iter().collect(), self.holders);
+        let points = self.players.iter().map(|player| scoreboard.victory_points(player)).collect();
+        self.holders = scoreboard.get_holders();
+        points
+    }
+}
+
+// Play a number of games between bots with the given strategies and log how often each player won.
+// The strategies are a comma separated list of names, which are handed out to the players in turn.
+pub fn run_local_games(games: u32, player_count: usize, max_turns: u32, strategies: &str, seed: u64) -> Result<(), String> {
+    let names: Vec<&str> = strategies.split(',').map(str::trim).collect();
+    let mut wins = vec![0; player_count];
+    let mut unfinished = 0;
+    for game in 0..games {
+        let game_seed = seed.wrapping_add(game as u64);
+        let strategies = (0..player_count).map(|player| {
+            let strategy = names[player % names.len()];
+            create_strategy(strategy, game_seed.wrapping_mul(31).wrapping_add(player as u64))
+                .ok_or(format!("unknown strategy {}, choose from {:?}", strategy, STRATEGY_NAMES))
+        }).collect::<Result<Vec<Box<dyn Strategy>>, String>>()?;
+        let result = LocalGame::new(strategies, game_seed).play(max_turns);
+        match result.winner {
+            Some(winner) => {
+                info!("Game {}: player {} won after {} turns with points {:?}", game, winner, result.turns, result.points);
+                wins[winner] += 1;
+            },
+            None => {
+                info!("Game {}: no winner after {} turns, points {:?}", game, result.turns, result.points);
+                unfinished += 1;
+            }
+        }
+    }
+    info!("Wins per player: {:?}, unfinished games: {}", wins, unfinished);
+    Ok(())
+}
+
+fn is_valid_initial_build(graph: &BoardGraph, node_key: &str, edge_key: &str) -> bool {
+    let node = match graph.get_node_by_key(node_key) {
+        Some(node) => node,
+        None => return false,
+    };
+    graph.is_free_village_spot(node) && graph.get_edges_surrounding_node(node).into_iter().any(|edge| {
+        edge.key == edge_key && edge.player.is_none()
+    })
+}
+
+fn is_valid_street(graph: &BoardGraph, player: usize, edge_key: &str) -> bool {
+    let edge = match graph.get_edge_by_key(edge_key) {
+        Some(edge) => edge,
+        None => return false,
+    };
+    if edge.player.is_some() {
+        return false
+    }
+
+    // the street should connect to a building of the player, or to one of its streets as long
+    // as there is no building of an opponent in between
+    graph.get_nodes_surrounding_edge(edge).into_iter().any(|node| {
+        match node.player {
+            Some(owner) => owner == player,
+            None => graph.get_edges_surrounding_node(node).into_iter().any(|e| e.player == Some(player)),
+        }
+    })
+}
+
+fn is_valid_village(graph: &BoardGraph, player: usize, node_key: &str) -> bool {
+    let node = match graph.get_node_by_key(node_key) {
+        Some(node) => node,
+        None => return false,
+    };
+    graph.is_free_village_spot(node) && graph.get_edges_surrounding_node(node).into_iter().any(|edge| {
+        edge.player == Some(player)
+    })
+}
+
+fn give_resource(player: &mut Player, resource: ResourceKind, amount: usize) {
+    if let Some(r) = player.resources.iter_mut().find(|r| r.r#type == resource) {
+        r.value += amount;
+    }
+}
+
+fn take_resource(player: &mut Player, resource: ResourceKind, amount: usize) {
+    if let Some(r) = player.resources.iter_mut().find(|r| r.r#type == resource) {
+        r.value -= amount.min(r.value);
+    }
+}
+
+// Tiles are laid out in axial coordinates (q, r), with a ring of water around the land tiles.
+// The keys use offset coordinates so they fit in the u8 x and y of a tile.
+fn tile_key(q: i8, r: i8) -> String {
+    format!("[{},{}]", q + BOARD_RADIUS + 1, r + BOARD_RADIUS + 1)
+}
+
+fn is_on_board(q: i8, r: i8, radius: i8) -> bool {
+    q.abs() <= radius && r.abs() <= radius && (q + r).abs() <= radius
+}
+
+// Create the default board: 19 land tiles surrounded by water, with the nodes and edges between
+// them keyed the same way as the catan-server does.
+fn create_board(rng: &mut StdRng) -> (Vec<Tile>, Vec<Node>, Vec<Edge>) {
+    let mut resource_types = vec!(TileKind::Desert);
+    resource_types.extend(vec![TileKind::Wood; 4]);
+    resource_types.extend(vec![TileKind::Wool; 4]);
+    resource_types.extend(vec![TileKind::Grain; 4]);
+    resource_types.extend(vec![TileKind::Stone; 3]);
+    resource_types.extend(vec![TileKind::Ore; 3]);
+    resource_types.shuffle(rng);
+    let mut numbers: Vec<u32> = vec!(2, 3, 3, 4, 4, 5, 5, 6, 6, 8, 8, 9, 9, 10, 10, 11, 11, 12);
+    numbers.shuffle(rng);
+
+    let coordinates: Vec<(i8, i8)> = (-BOARD_RADIUS - 1..=BOARD_RADIUS + 1).flat_map(|q| {
+        (-BOARD_RADIUS - 1..=BOARD_RADIUS + 1).map(move |r| (q, r))
+    }).filter(|(q, r)| is_on_board(*q, *r, BOARD_RADIUS + 1)).collect();
+
+    let mut tiles = Vec::new();
+    for (q, r) in &coordinates {
+        let (resource_type, number) = if is_on_board(*q, *r, BOARD_RADIUS) {
+            let resource_type = resource_types.pop().unwrap_or(TileKind::Desert);
+            let number = if resource_type == TileKind::Desert { 0 } else { numbers.pop().unwrap_or(0) };
+            (resource_type, number)
+        } else {
+            (TileKind::Water, 0)
+        };
+        tiles.push(Tile {
+            key: tile_key(*q, *r),
+            resource_type,
+            number,
+            x: (q + BOARD_RADIUS + 1) as u8,
+            y: (r + BOARD_RADIUS + 1) as u8,
+        });
+    }
+
+    // nodes and edges only exist when all their tiles are on the board and one of them is land
+    let is_valid = |positions: &[(i8, i8)]| {
+        positions.iter().all(|(q, r)| is_on_board(*q, *r, BOARD_RADIUS + 1))
+            && positions.iter().any(|(q, r)| is_on_board(*q, *r, BOARD_RADIUS))
+    };
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for (q, r) in &coordinates {
+        let (q, r) = (*q, *r);
+
+        // every node is either the top corner or the bottom corner of exactly one tile
+        let corners = [
+            [(q, r), (q, r - 1), (q + 1, r - 1)],
+            [(q, r), (q - 1, r + 1), (q, r + 1)],
+        ];
+        for corner in corners.iter() {
+            if is_valid(corner) {
+                let (t_key, l_key, r_key) = (tile_key(corner[0].0, corner[0].1), tile_key(corner[1].0, corner[1].1), tile_key(corner[2].0, corner[2].1));
+                nodes.push(Node {
+                    key: format!("({},{},{})", t_key, l_key, r_key),
+                    structure: None,
+                    player: None,
+                    t_key,
+                    r_key,
+                    l_key,
+                });
+            }
+        }
+
+        for (dq, dr) in DIRECTIONS.iter() {
+            let neighbour = (q + dq, r + dr);
+            if is_valid(&[(q, r), neighbour]) {
+                edges.push(Edge {
+                    key: format!("({},{})", tile_key(q, r), tile_key(neighbour.0, neighbour.1)),
+                    player: None,
+                    road: false,
+                });
+            }
+        }
+    }
+
+    (tiles, nodes, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::*;
+    use itertools::Itertools;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // Plays like a RandomStrategy and remembers where it placed its initial villages
+    struct RecordingStrategy {
+        random: RandomStrategy,
+        villages: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Strategy for RecordingStrategy {
+        fn trade(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<TradeCommand>, ClientError> {
+            self.random.trade(game, graph)
+        }
+
+        fn build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+            self.random.build(game, graph)
+        }
+
+        fn initial_build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+            let commands = self.random.initial_build(game, graph)?;
+            self.villages.borrow_mut().extend(commands.iter()
+                .filter(|command| command.structure == StructureKind::Village)
+                .map(|command| command.location.clone()));
+            Ok(commands)
+        }
+
+        fn move_bandit(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<MoveBanditCommand>, ClientError> {
+            self.random.move_bandit(game, graph)
+        }
+
+        fn discard(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<Resource>, ClientError> {
+            self.random.discard(game, graph)
+        }
+    }
+
+    fn get_strategies(names: &[&str]) -> Vec<Box<dyn Strategy>> {
+        names.iter().enumerate().filter_map(|(player, name)| create_strategy(name, player as u64)).collect()
+    }
+
+    #[test]
+    fn plays_a_seeded_game_to_the_end() {
+        let result = LocalGame::new(get_strategies(&["random", "heuristic"]), 1).play(500);
+        assert_eq!(result.points.len(), 2);
+        let winner = result.winner.expect("the game was not finished after 500 rounds");
+        assert!(result.points[winner] >= WINNING_POINTS);
+        assert!(result.turns <= 500);
+
+        // the same seed plays the same game
+        let replay = LocalGame::new(get_strategies(&["random", "heuristic"]), 1).play(500);
+        assert_eq!((replay.winner, replay.turns, replay.points), (result.winner, result.turns, result.points));
+    }
+
+    #[test]
+    fn initial_placements_keep_their_distance_and_pay_the_second_village() {
+        let villages: Vec<Rc<RefCell<Vec<String>>>> = (0..3).map(|_| Rc::new(RefCell::new(Vec::new()))).collect();
+        let strategies: Vec<Box<dyn Strategy>> = villages.iter().enumerate().map(|(player, villages)| {
+            Box::new(RecordingStrategy { random: RandomStrategy::new(player as u64), villages: villages.clone() }) as Box<dyn Strategy>
+        }).collect();
+        let mut game = LocalGame::new(strategies, 7);
+        game.place_initial_buildings();
+
+        let graph = BoardGraph::new(game.board());
+        for node in graph.get_nodes().iter().filter(|node| node.player.is_some()) {
+            assert!(graph.get_nodes_surrounding_node(node).into_iter().all(|other| other.player.is_none()), "{} has a neighbour", node.key);
+        }
+
+        for (player, villages) in villages.iter().enumerate() {
+            let villages = villages.borrow();
+            assert_eq!(villages.len(), 2);
+            assert_eq!(game.get_nodes_from_player(player).len(), 2);
+            assert_eq!(graph.get_edges().iter().filter(|edge| edge.player == Some(player)).count(), 2);
+
+            let second = graph.get_node_by_key(&villages[1]).unwrap();
+            let mut expected: Vec<ResourceKind> = graph.get_tiles_surrounding_node(second).into_iter()
+                .filter_map(|tile| tile.resource_type.resource())
+                .collect();
+            let mut resources: Vec<ResourceKind> = game.players[player].resources.iter()
+                .flat_map(|resource| std::iter::repeat_n(resource.r#type, resource.value))
+                .collect();
+            expected.sort();
+            resources.sort();
+            assert_eq!(resources, expected);
+        }
+    }
+
+    // the port edges of the default board, the coast is the same with and without the sea around it
+    fn get_port_edges(keep_water: bool) -> Vec<String> {
+        let (tiles, nodes, edges) = create_board(&mut StdRng::seed_from_u64(0));
+        let tiles = tiles.into_iter().filter(|tile| keep_water || tile.resource_type.is_land()).collect();
+        let mut board = Board::new(tiles, nodes, edges, Vec::new(), Vec::new());
+        board.add_default_ports(PortLayout::Standard);
+        board.get_ports().into_iter().map(|port| port.edge_key.clone()).collect()
+    }
+
+    #[test]
+    fn places_the_ports_on_the_coast() {
+        let port_edges = get_port_edges(true);
+        // the nine ports of the base game, each on its own edge
+        assert_eq!(port_edges.iter().unique().count(), 9);
+        assert_eq!(get_port_edges(false), port_edges);
+
+        let (tiles, nodes, edges) = create_board(&mut StdRng::seed_from_u64(0));
+        let board = Board::new(tiles, nodes, edges, Vec::new(), Vec::new());
+        for edge_key in port_edges {
+            // an edge on the coast has a node with water around it on either end
+            let edge = board.get_edge_by_key(&edge_key).unwrap();
+            let nodes = board.get_nodes_surrounding_edge(edge);
+            assert_eq!(nodes.len(), 2);
+            for node in nodes {
+                assert!(board.get_tiles_surrounding_node(node).iter().any(|tile| !tile.resource_type.is_land()), "{}", edge_key);
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt;
+use std::io;
+
+// Everything that can go wrong while talking to the server or deciding what to send back
+#[derive(Debug)]
+pub enum ClientError {
+    // reading from or writing to the connection failed, the connection is probably gone
+    Io(io::Error),
+    // a message could not be turned into json or back
+    Json(serde_json::Error),
+    // the server sent something that does not fit the protocol
+    Protocol(String),
+    // something we need to answer is not known yet, like the board or our own player
+    MissingState(&'static str),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "connection error: {}", e),
+            ClientError::Json(e) => write!(f, "invalid json: {}", e),
+            ClientError::Protocol(message) => write!(f, "protocol error: {}", message),
+            ClientError::MissingState(what) => write!(f, "missing state: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> ClientError {
+        ClientError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ClientError {
+    fn from(e: serde_json::Error) -> ClientError {
+        ClientError::Json(e)
+    }
+}
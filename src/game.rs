@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use crate::board::*;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ServerInputEvent {
+    pub model: String,
+    // left untyped, so an event we cannot read does not take the rest of the snapshot with it
+    pub attributes: serde_json::Value,
+}
+
+// Something that happened in the game, as the server logs it. See GameEvent for the typed version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Event {
+    pub event_type: String,
+    pub move_count: Option<u32>,
+    #[serde(default)]
+    pub message: String,
+    pub player: usize,
+    #[serde(default)]
+    pub resources: Vec<ResourceKind>,
+    #[serde(default)]
+    pub structures: Vec<StructureKind>,
+    // the card that was bought or played
+    #[serde(default)]
+    pub development_card: Option<DevelopmentCardKind>,
+    // the resource a monopoly was played on, the resources are empty when nobody had any
+    #[serde(default)]
+    pub monopoly: Option<ResourceKind>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GameStatus {
+    Waiting,
+    Running,
+    Finished,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePhase {
+    InitialBuild,
+    ThrowDice,
+    ForceDiscard,
+    MoveBandit,
+    Trade,
+    Build,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Game {
+    pub move_count: Option<u32>,
+    pub players: Vec<ServerInputPlayer>,
+    pub status: GameStatus,
+    pub board: Option<ServerInputBoard>,
+    pub events: Option<Vec<ServerInputEvent>>,
+    pub last_dice_throw: Option<u8>,
+    pub phase: Option<GamePhase>,
+    pub current_player: Option<u8>,
+
+    #[serde(skip)] 
+    my_id: i16,
+}
+
+impl Game {
+    pub fn new(players: Vec<Player>, board: Board) -> Game {
+        Game {
+            move_count: None,
+            players: players.into_iter().map(|player| ServerInputPlayer { model: String::from("player"), attributes: player }).collect(),
+            status: GameStatus::Running,
+            board: Some(ServerInputBoard { model: String::from("board"), attributes: board }),
+            events: None,
+            last_dice_throw: None,
+            phase: None,
+            current_player: None,
+            my_id: -1,
+        }
+    }
+
+    pub fn get_board(&self) -> Option<&Board> {
+        if let Some(board) = &self.board {
+            Some(&board.attributes)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_my_id(&mut self, id: i16) {
+        self.my_id = id;
+    }
+
+    pub fn get_players(&self) -> Vec<&Player> {
+        self.players.iter().map(|player_model| {
+            &player_model.attributes
+        }).collect()
+    }
+
+    // returns the player belonging to this codebase, if it exists in the data
+    pub fn me(&self) -> Option<&Player> {
+        self.get_player_by_id(self.my_id as usize)
+    }
+
+    pub fn get_events(&self) -> Vec<&serde_json::Value> {
+        match &self.events {
+            Some(events) => events.iter().map(|event| &event.attributes).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn get_player_by_id(&self, id: usize) -> Option<&Player> {
+        self.get_players().into_iter().find(|p| { p.id == id })
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use crate::board::*;
+
+// An index over a Board. The Board itself only has lists of tiles, nodes and edges, so every
+// query has to scan them. The graph takes the board of a game snapshot over and keeps maps from keys
+// to positions and the adjacency between tiles, nodes and edges, so all lookups are O(1). It is
+// built once per snapshot and kept next to the game.
+pub struct BoardGraph {
+    board: Board,
+    tile_indices: HashMap<String, usize>,
+    node_indices: HashMap<String, usize>,
+    edge_indices: HashMap<String, usize>,
+    node_tiles: Vec<Vec<usize>>,
+    node_edges: Vec<Vec<usize>>,
+    node_nodes: Vec<Vec<usize>>,
+    edge_nodes: Vec<Vec<usize>>,
+    tile_nodes: Vec<Vec<usize>>,
+}
+
+impl BoardGraph {
+    pub fn new(board: Board) -> BoardGraph {
+        let tiles = board.get_tiles();
+        let nodes = board.get_nodes();
+        let edges = board.get_edges();
+        let tile_indices: HashMap<String, usize> = tiles.iter().enumerate().map(|(i, tile)| (tile.key.clone(), i)).collect();
+        let node_indices: HashMap<String, usize> = nodes.iter().enumerate().map(|(i, node)| (node.key.clone(), i)).collect();
+        let edge_indices: HashMap<String, usize> = edges.iter().enumerate().map(|(i, edge)| (edge.key.clone(), i)).collect();
+
+        let mut node_tiles = Vec::with_capacity(nodes.len());
+        let mut node_edges = Vec::with_capacity(nodes.len());
+        let mut edge_nodes = vec![Vec::new(); edges.len()];
+        let mut tile_nodes = vec![Vec::new(); tiles.len()];
+        for (node_index, node) in nodes.iter().enumerate() {
+            let surrounding_tiles: Vec<usize> = [&node.t_key, &node.l_key, &node.r_key].iter()
+                .filter_map(|key| tile_indices.get(key.as_str()).cloned())
+                .collect();
+
+            // an edge is keyed by the two tiles it lies between, in either order
+            let mut surrounding_edges = Vec::new();
+            for (i, first) in surrounding_tiles.iter().enumerate() {
+                for second in &surrounding_tiles[i + 1..] {
+                    let (first_key, second_key) = (&tiles[*first].key, &tiles[*second].key);
+                    let edge = edge_indices.get(format!("({},{})", first_key, second_key).as_str())
+                        .or_else(|| edge_indices.get(format!("({},{})", second_key, first_key).as_str()));
+                    if let Some(edge) = edge {
+                        surrounding_edges.push(*edge);
+                        edge_nodes[*edge].push(node_index);
+                    }
+                }
+            }
+            for tile in &surrounding_tiles {
+                tile_nodes[*tile].push(node_index);
+            }
+            node_tiles.push(surrounding_tiles);
+            node_edges.push(surrounding_edges);
+        }
+
+        let node_nodes = (0..nodes.len()).map(|node_index| {
+            node_edges[node_index].iter().flat_map(|edge| {
+                edge_nodes[*edge].iter().cloned().filter(|other| *other != node_index)
+            }).collect()
+        }).collect();
+
+        BoardGraph {
+            board,
+            tile_indices,
+            node_indices,
+            edge_indices,
+            node_tiles,
+            node_edges,
+            node_nodes,
+            edge_nodes,
+            tile_nodes,
+        }
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_tiles(&self) -> Vec<&Tile> {
+        self.board.get_tiles()
+    }
+
+    pub fn get_nodes(&self) -> Vec<&Node> {
+        self.board.get_nodes()
+    }
+
+    pub fn get_edges(&self) -> Vec<&Edge> {
+        self.board.get_edges()
+    }
+
+    pub fn get_tile_by_key(&self, key: &str) -> Option<&Tile> {
+        self.tile_indices.get(key).map(|i| self.get_tile(*i))
+    }
+
+    pub fn get_node_by_key(&self, key: &str) -> Option<&Node> {
+        self.node_indices.get(key).map(|i| self.get_node(*i))
+    }
+
+    pub fn get_edge_by_key(&self, key: &str) -> Option<&Edge> {
+        self.edge_indices.get(key).map(|i| self.get_edge(*i))
+    }
+
+    pub fn get_tiles_surrounding_node(&self, node: &Node) -> Vec<&Tile> {
+        self.get_neighbours(&self.node_indices, &self.node_tiles, &node.key).map(|i| self.get_tile(i)).collect()
+    }
+
+    // get edges leading to a node
+    pub fn get_edges_surrounding_node(&self, node: &Node) -> Vec<&Edge> {
+        self.get_neighbours(&self.node_indices, &self.node_edges, &node.key).map(|i| self.get_edge(i)).collect()
+    }
+
+    // get the nodes at both ends of an edge
+    pub fn get_nodes_surrounding_edge(&self, edge: &Edge) -> Vec<&Node> {
+        self.get_neighbours(&self.edge_indices, &self.edge_nodes, &edge.key).map(|i| self.get_node(i)).collect()
+    }
+
+    // get all nodes one edge away from a node
+    pub fn get_nodes_surrounding_node(&self, node: &Node) -> Vec<&Node> {
+        self.get_neighbours(&self.node_indices, &self.node_nodes, &node.key).map(|i| self.get_node(i)).collect()
+    }
+
+    // get the corners of a tile
+    pub fn get_nodes_surrounding_tile(&self, tile: &Tile) -> Vec<&Node> {
+        self.get_neighbours(&self.tile_indices, &self.tile_nodes, &tile.key).map(|i| self.get_node(i)).collect()
+    }
+
+    pub fn get_edges_from_player(&self, player: &Player) -> Vec<&Edge> {
+        self.board.get_edges_from_player(player)
+    }
+
+    // get the villages of a player that can be upgraded to a city
+    pub fn get_villages_from_player(&self, player: &Player) -> Vec<&Node> {
+        self.board.get_villages_from_player(player)
+    }
+
+    // the ports on the edges next to the node
+    pub fn get_ports_of_node(&self, node: &Node) -> Vec<&Port> {
+        let edges = self.get_edges_surrounding_node(node);
+        self.board.get_ports().into_iter().filter(|port| edges.iter().any(|edge| edge.key == port.edge_key)).collect()
+    }
+
+    pub fn get_cities_from_player(&self, player: &Player) -> Vec<&Node> {
+        self.board.get_cities_from_player(player)
+    }
+
+    // the number of dice combinations that make a building on the node produce something
+    pub fn get_pips_of_node(&self, node: &Node) -> u32 {
+        self.get_tiles_surrounding_node(node).into_iter().map(|tile| tile.get_pips()).sum()
+    }
+
+    // get all edges where the player could try to build a street
+    pub fn get_potential_street_edges(&self, player: &Player) -> Vec<&Edge> {
+        let mut edges: Vec<&Edge> = self.get_edges_from_player(player).into_iter()
+            .flat_map(|street| self.get_nodes_surrounding_edge(street))
+            // a village of an opponent breaks the road
+            .filter(|node| node.player.is_none() || node.player == Some(player.id))
+            .flat_map(|node| self.get_edges_surrounding_node(node))
+            .filter(|edge| edge.player.is_none())
+            .collect();
+        edges.sort();
+        edges.dedup();
+        edges
+    }
+
+    // get all nodes where the player can build a village
+    pub fn get_potential_village_nodes(&self, player: &Player) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = self.get_edges_from_player(player).into_iter()
+            .flat_map(|street| self.get_nodes_surrounding_edge(street))
+            .filter(|node| self.is_free_village_spot(node))
+            .collect();
+        nodes.sort_by(|a, b| a.key.cmp(&b.key));
+        nodes.dedup();
+        nodes
+    }
+
+    // a village can only be built on an empty node that has no buildings next to it
+    pub fn is_free_village_spot(&self, node: &Node) -> bool {
+        node.player.is_none() && self.get_nodes_surrounding_node(node).into_iter().all(|n| n.player.is_none())
+    }
+
+    // The length of the longest road of the player: the longest chain of its streets that uses
+    // every street at most once. A village or city of an opponent breaks the road.
+    pub fn get_longest_road(&self, player: &Player) -> usize {
+        self.get_longest_road_with(player, None)
+    }
+
+    // The free street next to our road that makes the longest road, and its length
+    pub fn get_best_road_extension(&self, player: &Player) -> Option<(&Edge, usize)> {
+        self.get_road_extensions(player).into_iter().next()
+    }
+
+    // The free streets next to our road with the length of the longest road after building them,
+    // the longest first
+    pub fn get_road_extensions(&self, player: &Player) -> Vec<(&Edge, usize)> {
+        let mut extensions: Vec<(&Edge, usize)> = self.get_potential_street_edges(player).into_iter()
+            .filter_map(|edge| self.edge_indices.get(edge.key.as_str()).map(|index| (edge, self.get_longest_road_with(player, Some(*index)))))
+            .collect();
+        extensions.sort_by(|(_, a), (_, b)| b.cmp(a));
+        extensions
+    }
+
+    // the longest road when the extra edge would be ours as well
+    fn get_longest_road_with(&self, player: &Player, extra: Option<usize>) -> usize {
+        let is_street = |edge: usize| extra == Some(edge) || (self.get_edge(edge).road && self.get_edge(edge).player == Some(player.id));
+        let mut used = vec![false; self.board.edges.len()];
+        let mut longest = 0;
+        for street in (0..self.board.edges.len()).filter(|edge| is_street(*edge)) {
+            // walk away from both ends of the street in turn
+            for node in &self.edge_nodes[street] {
+                used[street] = true;
+                longest = longest.max(1 + self.extend_road(player.id, *node, &is_street, &mut used));
+                used[street] = false;
+            }
+        }
+        longest
+    }
+
+    fn extend_road(&self, player_id: usize, node: usize, is_street: &dyn Fn(usize) -> bool, used: &mut Vec<bool>) -> usize {
+        let owner = self.get_node(node).player;
+        if self.get_node(node).structure.is_some() && owner.is_some_and(|owner| owner != player_id) {
+            return 0
+        }
+        let mut longest = 0;
+        for edge in &self.node_edges[node] {
+            if used[*edge] || !is_street(*edge) {
+                continue
+            }
+            used[*edge] = true;
+            for next in self.edge_nodes[*edge].iter().filter(|next| **next != node) {
+                longest = longest.max(1 + self.extend_road(player_id, *next, is_street, used));
+            }
+            used[*edge] = false;
+        }
+        longest
+    }
+
+    fn get_tile(&self, index: usize) -> &Tile {
+        &self.board.tiles[index].attributes
+    }
+
+    fn get_node(&self, index: usize) -> &Node {
+        &self.board.nodes[index].attributes
+    }
+
+    fn get_edge(&self, index: usize) -> &Edge {
+        &self.board.edges[index].attributes
+    }
+
+    // the positions of the neighbours of the item with the key, none when the key is unknown
+    fn get_neighbours<'b>(&self, indices: &HashMap<String, usize>, adjacency: &'b [Vec<usize>], key: &str) -> impl Iterator<Item = usize> + 'b {
+        indices.get(key).map_or(&[][..], |index| &adjacency[*index][..]).iter().cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::*;
+
+    // The graph answers the queries of the board from its index, so both should agree on every
+    // query. What the answers should be is tested on the board.
+    #[test]
+    fn answers_like_the_board() {
+        let board = two_player_board();
+        let graph = BoardGraph::new(board.clone());
+        let get_tile_keys = |tiles: Vec<&Tile>| -> Vec<String> { sorted(tiles.into_iter().map(|tile| tile.key.clone()).collect()) };
+
+        for node in board.get_nodes() {
+            assert_eq!(get_tile_keys(graph.get_tiles_surrounding_node(node)), get_tile_keys(board.get_tiles_surrounding_node(node)));
+            assert_eq!(get_edge_keys(graph.get_edges_surrounding_node(node)), get_edge_keys(board.get_edges_surrounding_node(node)));
+            assert_eq!(get_node_keys(graph.get_nodes_surrounding_node(node)), get_node_keys(board.get_nodes_surrounding_node(node)));
+        }
+        for edge in board.get_edges() {
+            assert_eq!(graph.get_edge_by_key(&edge.key).map(|edge| &edge.key), Some(&edge.key));
+            assert_eq!(get_node_keys(graph.get_nodes_surrounding_edge(edge)), get_node_keys(board.get_nodes_surrounding_edge(edge)));
+        }
+        for tile in board.get_tiles() {
+            assert_eq!(graph.get_tile_by_key(&tile.key).map(|tile| &tile.key), Some(&tile.key));
+        }
+        for player in [get_player(0), get_player(1)].iter() {
+            assert_eq!(get_edge_keys(graph.get_edges_from_player(player)), get_edge_keys(board.get_edges_from_player(player)));
+            assert_eq!(get_edge_keys(graph.get_potential_street_edges(player)), get_edge_keys(board.get_potential_street_edges(player)));
+            assert_eq!(get_node_keys(graph.get_potential_village_nodes(player)), get_node_keys(board.get_potential_village_nodes(player)));
+        }
+    }
+
+    fn get_extension_keys(extensions: &[(&Edge, usize)], length: usize) -> Vec<String> {
+        sorted(extensions.iter().filter(|(_, l)| *l == length).map(|(edge, _)| edge.key.clone()).collect())
+    }
+
+    fn set_street(board: &mut Board, key: &str, player: usize) {
+        let edge = &mut board.edges.iter_mut().find(|edge| edge.attributes.key == key).unwrap().attributes;
+        edge.player = Some(player);
+        edge.road = true;
+    }
+
+    fn set_building(board: &mut Board, key: &str, player: usize, structure: StructureKind) {
+        let node = &mut board.nodes.iter_mut().find(|node| node.attributes.key == key).unwrap().attributes;
+        node.player = Some(player);
+        node.structure = Some(structure);
+    }
+
+    #[test]
+    fn measures_the_roads_of_the_fixture() {
+        let graph = BoardGraph::new(two_player_board());
+        assert_eq!(graph.get_longest_road(&get_player(0)), 4);
+        assert_eq!(graph.get_longest_road(&get_player(1)), 2);
+    }
+
+    #[test]
+    fn extends_the_road_at_its_free_end() {
+        let graph = BoardGraph::new(two_player_board());
+        let extensions = graph.get_road_extensions(&get_player(0));
+        // both free edges at n0 make the road longer, p0 is the edge (t0,t1)
+        assert_eq!(get_extension_keys(&extensions, 5), sorted(vec!(path_edge(0), dangling_edge(0))));
+        assert_eq!(graph.get_best_road_extension(&get_player(0)).map(|(_, length)| length), Some(5));
+        // a branch in the middle does not make the road longer, and nothing can be built past the
+        // village of player 1 on n4
+        assert_eq!(get_extension_keys(&extensions, 4), sorted(vec!(dangling_edge(1), dangling_edge(2), dangling_edge(3))));
+        assert_eq!(extensions.len(), 5);
+    }
+
+    #[test]
+    fn cuts_the_road_at_the_village_on_n4() {
+        let mut board = two_player_board();
+        // player 0 also gets the streets of player 1, they only connect through its village
+        set_street(&mut board, &path_edge(5), 0);
+        set_street(&mut board, &path_edge(6), 0);
+        let graph = BoardGraph::new(board);
+        assert_eq!(graph.get_longest_road(&get_player(0)), 4);
+    }
+
+    #[test]
+    fn cuts_the_road_at_an_opponent_village_in_the_middle() {
+        let mut board = two_player_board();
+        set_building(&mut board, "n2", 1, StructureKind::Village);
+        let graph = BoardGraph::new(board.clone());
+        // p1 - p2 and p3 - p4 are on both sides of n2
+        assert_eq!(graph.get_longest_road(&get_player(0)), 2);
+
+        // a building of our own does not cut the road
+        set_building(&mut board, "n2", 0, StructureKind::City);
+        assert_eq!(BoardGraph::new(board).get_longest_road(&get_player(0)), 4);
+    }
+}
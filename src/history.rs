@@ -0,0 +1,185 @@
+use serde::Deserialize;
+
+use crate::board::*;
+use crate::game::*;
+
+// Every snapshot of the game contains the events of the last moves, the history collects them so
+// we know what the other players did during the whole game.
+
+// An event of the game log, with the untyped parts of the server event turned into enums
+#[derive(Clone, PartialEq, Debug)]
+pub enum GameEvent {
+    // the message of the server ends with the thrown number
+    DiceThrow { player: usize, number: Option<u8> },
+    Build { player: usize, structures: Vec<StructureKind> },
+    // trades with the bank or a port give one resource for all the others
+    Trade { player: usize, given: Vec<ResourceKind>, received: Option<ResourceKind> },
+    MoveBandit { player: usize },
+    // only the thief and the victim know which resource was stolen, the message ends with the
+    // id of the victim
+    Steal { player: usize, victim: Option<usize> },
+    Discard { player: usize, resources: Vec<ResourceKind> },
+    // the other players do not get to know which card was bought
+    BuyDevelopmentCard { player: usize },
+    // the resources are the ones the player got from a year of plenty or a monopoly, the monopoly
+    // is the resource it took from the others, even when they had none
+    PlayDevelopmentCard { player: usize, card: Option<DevelopmentCardKind>, resources: Vec<ResourceKind>, monopoly: Option<ResourceKind> },
+    // also the events we cannot read, e.g. with a resource we do not know
+    Other { player: Option<usize>, message: String },
+}
+
+impl GameEvent {
+    pub fn from_event(event: &serde_json::Value) -> GameEvent {
+        match Event::deserialize(event) {
+            Ok(event) => GameEvent::from_typed_event(&event),
+            Err(_) => GameEvent::Other {
+                player: event.get("player").and_then(serde_json::Value::as_u64).map(|player| player as usize),
+                message: event.get("message").and_then(serde_json::Value::as_str).map_or_else(|| event.to_string(), String::from),
+            },
+        }
+    }
+
+    fn from_typed_event(event: &Event) -> GameEvent {
+        let player = event.player;
+        match event.event_type.as_str() {
+            "throw_dice" => GameEvent::DiceThrow {
+                player,
+                number: get_last_number(&event.message),
+            },
+            "build" => GameEvent::Build { player, structures: event.structures.clone() },
+            "trade" => {
+                let (received, given) = match event.resources.split_last() {
+                    Some((received, given)) => (Some(*received), given.to_vec()),
+                    None => (None, Vec::new()),
+                };
+                GameEvent::Trade { player, given, received }
+            },
+            "move_bandit" => GameEvent::MoveBandit { player },
+            "steal" => GameEvent::Steal { player, victim: get_last_number(&event.message) },
+            "force_discard" => GameEvent::Discard { player, resources: event.resources.clone() },
+            "buy_development_card" => GameEvent::BuyDevelopmentCard { player },
+            "play_development_card" => GameEvent::PlayDevelopmentCard {
+                player,
+                card: event.development_card,
+                resources: event.resources.clone(),
+                monopoly: match event.development_card {
+                    Some(DevelopmentCardKind::Monopoly) => event.monopoly.or_else(|| event.resources.first().cloned()),
+                    _ => None,
+                },
+            },
+            _ => GameEvent::Other { player: Some(player), message: event.message.clone() },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub move_count: u32,
+    pub event: GameEvent,
+}
+
+#[derive(Default)]
+pub struct GameHistory {
+    entries: Vec<HistoryEntry>,
+    last_move_count: Option<u32>,
+    // a move can have more than one event, and a snapshot may be sent before all of them happened
+    seen_at_last_move_count: usize,
+}
+
+impl GameHistory {
+    // Add the events of a snapshot that are newer than everything we saw before and return them.
+    // Of the events with the last move count we saw, the ones after those we already have are new.
+    // Events without a move count cannot be ordered and are left out.
+    pub fn update(&mut self, game: &Game) -> &[HistoryEntry] {
+        let start = self.entries.len();
+        let last_move_count = self.last_move_count;
+        let mut at_last_move_count = 0;
+        for event in game.get_events() {
+            let move_count = match event.get("move_count").and_then(serde_json::Value::as_u64) {
+                Some(move_count) => move_count as u32,
+                None => continue,
+            };
+            match last_move_count {
+                Some(last) if move_count < last => continue,
+                Some(last) if move_count == last => {
+                    at_last_move_count += 1;
+                    if at_last_move_count <= self.seen_at_last_move_count {
+                        continue
+                    }
+                },
+                _ => {},
+            }
+            self.entries.push(HistoryEntry { move_count, event: GameEvent::from_event(event) });
+            if self.last_move_count == Some(move_count) {
+                self.seen_at_last_move_count += 1;
+            } else {
+                self.last_move_count = Some(move_count);
+                self.seen_at_last_move_count = 1;
+            }
+        }
+        &self.entries[start..]
+    }
+}
+
+fn get_last_number<T: std::str::FromStr>(message: &str) -> Option<T> {
+    message.split_whitespace().last().and_then(|word| word.trim_end_matches('.').parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_event(move_count: u32, message: &str) -> ServerInputEvent {
+        ServerInputEvent {
+            model: String::from("event"),
+            attributes: serde_json::json!({
+                "event_type": "other",
+                "move_count": move_count,
+                "message": message,
+                "player": 0,
+            }),
+        }
+    }
+
+    fn get_game(events: Vec<ServerInputEvent>) -> Game {
+        let mut game = Game::new(Vec::new(), Board::new(Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new()));
+        game.events = Some(events);
+        game
+    }
+
+    fn get_messages(entries: &[HistoryEntry]) -> Vec<String> {
+        entries.iter().map(|entry| match &entry.event {
+            GameEvent::Other { message, .. } => message.clone(),
+            event => format!("{:?}", event),
+        }).collect()
+    }
+
+    #[test]
+    fn keeps_later_events_of_the_last_move() {
+        let mut history = GameHistory::default();
+        assert_eq!(get_messages(history.update(&get_game(vec!(get_event(1, "a"), get_event(2, "b"))))), vec!("a", "b"));
+        // the second event of move 2 happened after the first snapshot
+        let game = get_game(vec!(get_event(1, "a"), get_event(2, "b"), get_event(2, "c"), get_event(3, "d")));
+        assert_eq!(get_messages(history.update(&game)), vec!("c", "d"));
+        assert!(history.update(&game).is_empty());
+    }
+
+    #[test]
+    fn reads_snapshots_with_events_it_does_not_understand() {
+        let input = r#"{"model": "game", "attributes": {"move_count": 3, "players": [], "status": "running", "last_dice_throw": 8,
+            "events": [
+                {"model": "event", "attributes": {"event_type": "throw_dice", "move_count": 1, "message": "Player 0 threw 8", "player": 0}},
+                {"model": "event", "attributes": {"event_type": "trade", "move_count": 2, "message": "Player 1 traded", "player": 1,
+                    "resources": ["gold", "wood"], "structures": []}},
+                {"model": "event", "attributes": {"event_type": "build", "move_count": 3}}
+            ]}}"#;
+        let game = match serde_json::from_str(input).unwrap() {
+            crate::server::InboundMessage::Game(game) => game,
+            _ => panic!("expected a game"),
+        };
+        let events: Vec<GameEvent> = GameHistory::default().update(&game).iter().map(|entry| entry.event.clone()).collect();
+        assert_eq!(events[0], GameEvent::DiceThrow { player: 0, number: Some(8) });
+        assert_eq!(events[1], GameEvent::Other { player: Some(1), message: String::from("Player 1 traded") });
+        assert!(matches!(events[2], GameEvent::Other { player: None, .. }));
+    }
+}
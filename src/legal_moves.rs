@@ -0,0 +1,114 @@
+use crate::board::*;
+use crate::commands::*;
+use crate::graph::*;
+use crate::server::*;
+use crate::trading::*;
+
+// Enumerates everything a player is allowed to do in response to a request of the server, so
+// strategies only have to pick from moves that the server will accept.
+
+#[derive(Debug)]
+pub enum Move {
+    Trade(TradeCommand),
+    Build(BuildCommand),
+    // an initial village together with a street next to it
+    InitialBuild(BuildCommand, BuildCommand),
+    Bandit(MoveBanditCommand),
+    // the resources to give away, adding up to half of the hand
+    Discard(Vec<Resource>),
+}
+
+// Get all legal moves of a player for one of the requests 100 - 104. Every build and trade is a
+// separate move, a strategy that wants to do more than one can ask again after applying one.
+pub fn legal_moves(graph: &BoardGraph, player: &Player, request: ResponseCode) -> Vec<Move> {
+    match request {
+        ResponseCode::TradeRequest => get_legal_trades(graph.get_board(), player),
+        ResponseCode::BuildRequest => get_legal_builds(graph, player),
+        ResponseCode::InitialBuildRequest => get_legal_initial_builds(graph),
+        ResponseCode::MoveBanditRequest => get_legal_bandit_moves(graph),
+        ResponseCode::ForceDiscardRequest => get_legal_discards(player),
+        _ => Vec::new(),
+    }
+}
+
+fn get_legal_trades(board: &Board, player: &Player) -> Vec<Move> {
+    let rates = TradeRates::for_player(board, player);
+    let mut moves = Vec::new();
+    for from in ResourceKind::ALL.iter() {
+        if player.get_resource_count(*from) < rates.get_ratio(*from) {
+            continue
+        }
+        for to in ResourceKind::ALL.iter().filter(|to| *to != from) {
+            moves.push(Move::Trade(TradeCommand { from: *from, to: *to }));
+        }
+    }
+    moves
+}
+
+fn get_legal_builds(graph: &BoardGraph, player: &Player) -> Vec<Move> {
+    let mut locations: Vec<(StructureKind, String)> = Vec::new();
+    if player.can_afford(StructureKind::Street) {
+        locations.extend(graph.get_potential_street_edges(player).into_iter().map(|edge| (StructureKind::Street, edge.key.clone())));
+    }
+    if player.can_afford(StructureKind::Village) {
+        locations.extend(graph.get_potential_village_nodes(player).into_iter().map(|node| (StructureKind::Village, node.key.clone())));
+    }
+    if player.can_afford(StructureKind::City) {
+        locations.extend(graph.get_villages_from_player(player).into_iter().map(|node| (StructureKind::City, node.key.clone())));
+    }
+
+    locations.into_iter().map(|(structure, location)| Move::Build(BuildCommand { structure, location })).collect()
+}
+
+// a village on any free spot, respecting the distance rule, and a street next to it
+fn get_legal_initial_builds(graph: &BoardGraph) -> Vec<Move> {
+    let mut moves = Vec::new();
+    for node in graph.get_nodes().iter().filter(|node| graph.is_free_village_spot(node)) {
+        for edge in graph.get_edges_surrounding_node(node).into_iter().filter(|edge| edge.player.is_none()) {
+            moves.push(Move::InitialBuild(
+                BuildCommand { structure: StructureKind::Village, location: node.key.clone() },
+                BuildCommand { structure: StructureKind::Street, location: edge.key.clone() },
+            ));
+        }
+    }
+    moves
+}
+
+// the bandit has to move to another land tile
+fn get_legal_bandit_moves(graph: &BoardGraph) -> Vec<Move> {
+    let bandits = graph.get_board().get_bandits();
+    graph.get_tiles().iter().filter(|tile| {
+        tile.resource_type.is_land() && !bandits.iter().any(|bandit| bandit.tile_key == tile.key)
+    }).map(|tile| Move::Bandit(MoveBanditCommand { location: tile.key.clone() })).collect()
+}
+
+// every way to give away half of the hand, rounded down
+fn get_legal_discards(player: &Player) -> Vec<Move> {
+    let counts: Vec<usize> = ResourceKind::ALL.iter().map(|resource| player.get_resource_count(*resource)).collect();
+    let mut discards = Vec::new();
+    add_discards(&counts, player.get_resource_total() / 2, &mut Vec::new(), &mut discards);
+
+    discards.into_iter().map(|amounts| {
+        Move::Discard(ResourceKind::ALL.iter().zip(amounts).filter(|(_, amount)| *amount > 0).map(|(resource, amount)| {
+            Resource { r#type: *resource, value: amount }
+        }).collect())
+    }).collect()
+}
+
+// choose how many of each resource to discard, one resource at a time
+fn add_discards(counts: &[usize], remaining: usize, chosen: &mut Vec<usize>, discards: &mut Vec<Vec<usize>>) {
+    if chosen.len() == counts.len() {
+        if remaining == 0 {
+            discards.push(chosen.clone());
+        }
+        return
+    }
+    let available_after: usize = counts[chosen.len() + 1..].iter().sum();
+    let max = counts[chosen.len()].min(remaining);
+    let min = remaining.saturating_sub(available_after);
+    for amount in min..=max {
+        chosen.push(amount);
+        add_discards(counts, remaining - amount, chosen, discards);
+        chosen.pop();
+    }
+}
@@ -1,301 +1,362 @@
+use std::collections::HashSet;
 use std::io::prelude::*;
 use bufstream::BufStream;
 use std::net::TcpStream;
-use serde::{Deserialize, Serialize};
-use rand::seq::SliceRandom;
-use num_derive::FromPrimitive;    
+use serde::Serialize;
 use num_traits::FromPrimitive;
-use std::{thread, time};
+use std::thread;
+use log::{debug, error, info, warn};
 
-use crate::server::*;
 use crate::board::*;
 use crate::commands::*;
+use crate::error::*;
+use crate::server::*;
+use crate::game::*;
+use crate::graph::*;
+use crate::strategy::*;
+use crate::config::*;
 
+mod bandit;
 mod board;
 mod server;
 mod commands;
+mod discard;
+mod error;
+mod game;
+mod graph;
+mod history;
+mod legal_moves;
+mod opponents;
+mod placement;
+mod scoring;
+mod strategy;
+mod trading;
+mod engine;
+#[cfg(test)]
+mod mock_server;
+mod config;
+mod logger;
+mod benchmark;
 
-#[derive(Serialize, Deserialize)]
-struct ServerInputEvent {
-    model: String,
-    attributes: serde_json::Value,
-}
-
-#[derive(Serialize, Deserialize)]
-struct Event {
-    event_type: String,
-    move_count: Option<u32>,
-    message: String,
-    player: u8,
-    resources: Vec<String>,
-    structures: Vec<String>,
-}
-
-#[derive(Serialize, Deserialize)]
-struct Game {
-    move_count: Option<u32>,
-    players: Vec<ServerInputPlayer>,
-    status: String,
-    board: Option<ServerInputBoard>,
-    events: Option<Vec<ServerInputEvent>>,
-    last_dice_throw: Option<u8>,
-    phase: Option<String>,
-    current_player: Option<u8>,
-
-    #[serde(skip)] 
-    my_id: i16,
-}
-
-impl Game {
-    fn get_board(&self) -> Option<&Board> {
-        if let Some(board) = &self.board {
-            Some(&board.attributes)
-        } else {
-            None
+fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match Config::from_args(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
         }
+    };
+    if config.show_help {
+        println!("{}", USAGE);
+        return Ok(())
     }
+    logger::init(config.log_level);
 
-    fn set_my_id(&mut self, id: i16) {
-        self.my_id = id;
-    }
+    let seed = config.seed.unwrap_or_else(rand::random);
+    info!("Using seed {}", seed);
 
-    fn get_players(&self) -> Vec<&Player> {
-        self.players.iter().map(|player_model| {
-            &player_model.attributes
-        }).collect()
+    match config.mode {
+        Mode::Local { games, players } => {
+            if let Err(e) = engine::run_local_games(games, players, 500, &config.strategy, seed) {
+                error!("{}", e);
+            }
+            return Ok(())
+        },
+        Mode::Bench { iterations } => {
+            benchmark::run_benchmarks(iterations, seed);
+            return Ok(())
+        },
+        Mode::Connect => {},
     }
 
-    // returns the player belonging to this codebase, if it exists in the data
-    fn me(&self) -> Option<&Player> {
-        self.get_player_by_id(self.my_id as usize)
+    if !STRATEGY_NAMES.contains(&config.strategy.as_str()) {
+        error!("Unknown strategy {}, choose from {:?}", config.strategy, STRATEGY_NAMES);
+        std::process::exit(2);
     }
 
-    fn get_player_by_id(&self, id: usize) -> Option<&Player> {
-        self.get_players().into_iter().find(|p| { p.id == id })
-    }
+    run_client(&config, seed)
 }
 
-fn main() -> std::io::Result<()> {
-
-    let mut stream;
-    let mut my_id = -1;
+// Connect to the server and play until the reconnect policy gives up. What we know about the
+// game survives a lost connection, so we can take our seat again.
+fn run_client(config: &Config, seed: u64) -> std::io::Result<()> {
+    // the strategy learns about the game it plays, so every game gets a new one
+    let new_strategy = || create_strategy(&config.strategy, seed).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("unknown strategy {}", config.strategy))
+    });
+    let mut strategy = new_strategy()?;
+    let mut state = ClientState::new(config.ports);
+    let mut failed_attempts = 0;
 
     loop {
-        stream = TcpStream::connect("localhost:10006")?;
-        let mut buf_stream = BufStream::new(&stream);
-
-        let name = String::from("Luke Skywalker");
-
-        let join = JoinMessage {
-            id: -1,
-            name: name.clone(),
-        };
-        let mut join_message = ServerInput {
-            model: String::from("join"),
-            attributes: serde_json::to_value(join)?
+        let stream = match TcpStream::connect((config.host.as_str(), config.port)) {
+            Ok(stream) => stream,
+            Err(e) => {
+                failed_attempts += 1;
+                if !config.reconnect.allows(failed_attempts) {
+                    return Err(e)
+                }
+                let delay = config.reconnect.get_delay(failed_attempts);
+                warn!("Could not connect to {}:{}: {}, trying again in {:?}", config.host, config.port, e, delay);
+                thread::sleep(delay);
+                continue
+            }
         };
-        transmit(&mut buf_stream, &stream, &join_message);
-
-        println!("Connected as {}. Waiting for game to start...", name);
-        let mut game: Option<Game> = None;
-        loop {
 
-            // limit speed of rust
-            let sleep_time = time::Duration::from_millis(100);
-            thread::sleep(sleep_time);
+        // a finished game has no seat to return to
+        if state.game.as_ref().is_some_and(|g| g.status == GameStatus::Finished) {
+            state = ClientState::new(config.ports);
+            strategy = new_strategy()?;
+        }
 
-            if let Some(input) = read_tcp_input(&mut buf_stream) {
-            
-                let response: ServerInput  = match serde_json::from_str(&input) {
-                    Ok(response)  => response,
-                    Err(_) => {
-                        print!("something went wrong with reading input: {}", &input );
-                        continue;
-                    },
-                };
+        let mut session = Session::new();
+        if let Err(e) = play_session(&stream, &config.name, &mut state, strategy.as_mut(), &mut session) {
+            warn!("{}", e);
+        }
+        warn!("Lost the connection to {}:{}", config.host, config.port);
+        if config.reconnect == ReconnectPolicy::Never {
+            return Ok(())
+        }
 
-                match response.model.as_str() {
-                    "game"  => {
-                        let val: Game = serde_json::from_value(response.attributes)?;
-                        game = Some(val);
-                        if let Some(g) = &mut game {
-                            g.set_my_id(my_id);
-                        }
-                    },
-                    "response" => {
-                        println!("Received input: {}", &input);
-                        let server_response: ServerResponse = serde_json::from_value(response.attributes)?;
-                        if let Some(g) = &game {
-                            handle_server_response(&mut my_id, &stream, &mut buf_stream, server_response, &g)
-                        }
-                    },
-                    _ => {
-                        println!("Got something unknown");
-                    }
-                };
-            }
+        // a server that accepts the connection and closes it right away is as good as no server,
+        // only a session in which we got into the game starts the backoff over
+        if session.joined {
+            failed_attempts = 0;
         }
+        failed_attempts += 1;
+        if !config.reconnect.allows(failed_attempts) {
+            return Ok(())
+        }
+        let delay = config.reconnect.get_delay(failed_attempts);
+        info!("Connecting again in {:?}", delay);
+        thread::sleep(delay);
     }
 }
 
-#[derive(FromPrimitive)]
-enum ResponseCode {
-    Ok = 0,
-    IdAcknowledgment = 1,
-    TradeRequest = 100,
-    BuildRequest = 101,
-    InitialBuildRequest = 102,
-    MoveBanditRequest = 103,
-    ForceDiscardRequest = 104,
+// What we know about the game. It survives a reconnect, so we can take our seat again and
+// continue where we left off
+struct ClientState {
+    my_id: i16,
+    game: Option<Game>,
+    // the index over the board of the game
+    graph: Option<BoardGraph>,
+    // the ports we assume when the board of the server has none
+    port_layout: PortLayout,
 }
 
-// Handles a server response with either a print statement in the console or a transmission back. 
-fn handle_server_response(my_id: &mut i16, stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, server_response: ServerResponse, game : &Game) -> () {
+impl ClientState {
+    fn new(port_layout: PortLayout) -> ClientState {
+        ClientState {
+            my_id: -1,
+            game: None,
+            graph: None,
+            port_layout,
+        }
+    }
+}
 
-    match FromPrimitive::from_i16(server_response.code) {
-        Some(ResponseCode::Ok) => println!("Success!"),
-        Some(ResponseCode::IdAcknowledgment) => {
-            let id: i16 = server_response.additional_info.parse().unwrap_or(-1);
-            *my_id = id;
-        },
-        Some(ResponseCode::TradeRequest) => send_trade_command(&stream, &mut buf_stream, game).unwrap(),
-        Some(ResponseCode::BuildRequest) => send_build_command(&stream, &mut buf_stream, game).unwrap(),
-        Some(ResponseCode::InitialBuildRequest) => send_initial_build_command(&stream, &mut buf_stream, game).unwrap(),
-        Some(ResponseCode::MoveBanditRequest) => move_bandit_command(&stream, &mut buf_stream, game).unwrap(),
-        Some(ResponseCode::ForceDiscardRequest) => send_force_discard_command(&stream, &mut buf_stream, game).unwrap(),
+// What we remember about the messages of the server during a single connection
+struct Session {
+    // whether the server acknowledged our id or sent us the game
+    joined: bool,
+    unknown_models: HashSet<String>,
+    // the last request we answered, an error of the server is about our answer to it
+    pending_request: Option<ResponseCode>,
+}
 
-        _ => println!("Got error: {}, {}, {}", server_response.code, server_response.title, server_response.description)
+impl Session {
+    fn new() -> Session {
+        Session {
+            joined: false,
+            unknown_models: HashSet::new(),
+            pending_request: None,
+        }
     }
 }
 
+// Join the game and answer the server until the connection is closed. Only I/O errors end the
+// session, anything else is logged and the next message is read.
+fn play_session(stream: &TcpStream, name: &str, state: &mut ClientState, strategy: &mut dyn Strategy, session: &mut Session) -> Result<(), ClientError> {
+    let mut buf_stream = BufStream::new(stream);
 
-fn send_client_response(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, response_data: serde_json::Value) -> Result<(), &'static str> {
-    let response = ClientResponse {
-        response: response_data
+    let join = JoinMessage {
+        id: state.my_id,
+        name: String::from(name),
     };
-    let mut client_response = ServerInput {
-        model: String::from("client-response"),
-        attributes: serde_json::to_value(response).unwrap()
-    };
-    transmit(&mut buf_stream, &stream, &client_response)
-}
+    transmit(&mut buf_stream, stream, &OutboundMessage::Join(join))?;
 
-// Send a command that responds to a TradeRequest
-fn send_trade_command(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, game: &Game) -> Result<(), &'static str> {
-    let all_resources = vec!("ore", "grain", "wool", "wood", "stone");
-    let wanted_resources = vec!("wood", "stone", "grain", "wool");
-    let random_trade = TradeCommand {
-        from: String::from(all_resources.choose(&mut rand::thread_rng()).unwrap().clone()),
-        to: String::from(wanted_resources.choose(&mut rand::thread_rng()).unwrap().clone()),
-    };
-    let trade_commands : Vec<&TradeCommand> = vec!(&random_trade);
-    send_client_response(&stream, &mut buf_stream, serde_json::to_value(trade_commands).unwrap())
+    if state.my_id == -1 {
+        info!("Connected as {}. Waiting for game to start...", name);
+    } else {
+        info!("Connected as {}. Rejoining with id {}", name, state.my_id);
+    }
+    // blocks until the server sends something, so requests are answered as soon as they arrive
+    while let Some(input) = read_tcp_input(&mut buf_stream) {
+        if input.trim().is_empty() {
+            continue
+        }
+        match handle_input(&input, stream, &mut buf_stream, state, strategy, session) {
+            Ok(()) => {},
+            Err(ClientError::Io(e)) => return Err(ClientError::Io(e)),
+            Err(e) => warn!("{}, while handling: {}", e, input.trim()),
+        }
+    }
+    Ok(())
 }
 
-// Send a command that responds to a ForceDiscardRequest
-// Discards all resources.
-fn send_force_discard_command(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, game: &Game) -> Result<(), &'static str> {
-    let me  = game.me().unwrap();
-    send_client_response(&stream, &mut buf_stream, serde_json::to_value(&me.resources).unwrap())
-}
+fn handle_input(input: &str, stream: &TcpStream, buf_stream: &mut BufStream<&TcpStream>, state: &mut ClientState, strategy: &mut dyn Strategy, session: &mut Session) -> Result<(), ClientError> {
+    // the server may know models we do not, there is no need to complain about every one
+    let raw: ServerInput = serde_json::from_str(input)?;
+    if !InboundMessage::MODELS.contains(&raw.model.as_str()) {
+        if session.unknown_models.insert(raw.model.clone()) {
+            warn!("Ignoring messages with unknown model {}, the first one was: {}", raw.model, raw.attributes);
+        }
+        return Ok(())
+    }
 
-// Send a command that response to a MoveBanditRequest
-// Places the bandit on a random tile.
-fn move_bandit_command(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, game: &Game) -> Result<(), &'static str> {
-    let board = game.get_board().unwrap();
-    let tiles = board.get_tiles();
-    let random_tile = tiles.choose(&mut rand::thread_rng()).unwrap();
-    let bandit_cmd = MoveBanditCommand {
-        location: random_tile.key.clone()
+    match serde_json::from_str(input)? {
+        InboundMessage::Game(mut val) => {
+            val.set_my_id(state.my_id);
+            if let Some(board) = &mut val.board {
+                board.attributes.add_default_ports(state.port_layout);
+            }
+            // the graph over the board is built once per snapshot, every request about it uses it
+            state.graph = val.get_board().cloned().map(BoardGraph::new);
+            if let Some(graph) = &state.graph {
+                strategy.observe(&val, graph);
+            }
+            state.game = Some(*val);
+            session.joined = true;
+        },
+        InboundMessage::Response(server_response) => {
+            debug!("Received input: {}", input.trim());
+            handle_server_response(stream, buf_stream, server_response, state, strategy, session)?;
+        },
+        InboundMessage::Message(message) => info!("Message: {}", message.message),
     };
-    let bandit_commands: Vec<&MoveBanditCommand> = vec!(&bandit_cmd);
-    send_client_response(&stream, &mut buf_stream, serde_json::to_value(bandit_commands).unwrap())
+    Ok(())
 }
 
-// send a command that responds to a InitialBuildRequest
-// Create a village and street at a random location
-fn send_initial_build_command(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, game: &Game) -> Result<(), &'static str> {
-    let board = game.get_board().unwrap();
-    let nodes = board.get_nodes();
-    let random_node = nodes.choose(&mut rand::thread_rng()).unwrap();
-    let surrounding_edges = board.get_edges_surrounding_node(random_node);
-    let random_street = surrounding_edges.choose(&mut rand::thread_rng()).unwrap();
-    let build_village = BuildCommand {
-        structure: String::from("village"),
-        location: (*random_node).key.clone()
-    };
-    let build_street = BuildCommand {
-        structure: String::from("street"),
-        location: (*random_street).key.clone()
-    };
-    let commands = vec!(&build_village, &build_street);
+// Handles a server response with either a print statement in the console or a transmission back.
+// The decisions themselves are left to the strategy.
+fn handle_server_response(stream: &TcpStream, buf_stream: &mut BufStream<&TcpStream>, server_response: ServerResponse, state: &mut ClientState, strategy: &mut dyn Strategy, session: &mut Session) -> Result<(), ClientError> {
+    let code: Option<ResponseCode> = FromPrimitive::from_i16(server_response.code);
 
-    send_client_response(&stream, &mut buf_stream, serde_json::to_value(commands).unwrap())
+    match code {
+        Some(ResponseCode::Ok) => {
+            info!("Success!");
+            session.pending_request = None;
+        },
+        Some(ResponseCode::IdAcknowledgment) => {
+            let id: i16 = server_response.additional_info.parse()
+                .map_err(|_| ClientError::Protocol(format!("invalid id {}", server_response.additional_info)))?;
+            state.my_id = id;
+            if let Some(g) = &mut state.game {
+                g.set_my_id(id);
+            }
+            session.joined = true;
+        },
+        Some(request) if request.is_request() => {
+            session.pending_request = Some(request);
+            respond_to_request(request, stream, buf_stream, state, strategy)?;
+        },
+        // We do not know the error codes of the server, only that it marks its errors. We do not
+        // answer again on our own: when the server wants another answer it asks the request again.
+        _ if server_response.is_error => {
+            warn!("Got error: {}, {}, {}, {}", server_response.code, server_response.title, server_response.description, server_response.additional_info);
+            if let (Some(request), Some(game)) = (session.pending_request.take(), &state.game) {
+                let rejection = Rejection {
+                    request,
+                    description: server_response.description,
+                    additional_info: server_response.additional_info,
+                };
+                strategy.rejected(game, &rejection);
+            }
+        },
+        _ => warn!("Got unknown response: {}, {}, {}", server_response.code, server_response.title, server_response.description)
+    }
+    Ok(())
 }
 
-// Send a build command that responds to BuildRequest.
-// If a village can be built, build a village. Otherwise try building a street, otherwise do
-// nothing. 
-fn send_build_command(stream: &TcpStream, mut buf_stream: &mut BufStream<&TcpStream>, game: &Game) -> Result<(), &'static str> {
-    let board = game.get_board().unwrap();
-
-    let me = match game.me() {
-        Some(me) => me,
-        None => {
-            println!("game: {:?}", game.get_players());
-            return Err("me() does not exist at this point?")
+// Ask the strategy what to do for one of the requests of the server and send it back. The server
+// waits for an answer, so when the strategy fails we still answer, with an empty list of commands
+fn respond_to_request(request: ResponseCode, stream: &TcpStream, buf_stream: &mut BufStream<&TcpStream>, state: &ClientState, strategy: &mut dyn Strategy) -> Result<(), ClientError> {
+    let commands = match (&state.game, &state.graph) {
+        (Some(game), Some(graph)) => get_commands(request, game, graph, strategy),
+        (Some(_), None) => Err(ClientError::MissingState("the board is not known yet")),
+        (None, _) => Err(ClientError::MissingState("got a request before the game started")),
+    };
+    let commands = match commands {
+        Ok(commands) => commands,
+        Err(e) => {
+            error!("Could not respond to {:?}: {}", request, e);
+            serde_json::Value::Array(Vec::new())
         }
     };
+    send_client_response(stream, buf_stream, commands)
+}
 
-    println!("I have resources: {:?}", me.resources);
-
+fn get_commands(request: ResponseCode, game: &Game, graph: &BoardGraph, strategy: &mut dyn Strategy) -> Result<serde_json::Value, ClientError> {
+    let commands = match request {
+        ResponseCode::TradeRequest => serde_json::to_value(strategy.trade(game, graph)?)?,
+        ResponseCode::BuildRequest => {
+            let me = game.me().ok_or(ClientError::MissingState("me() does not exist at this point?"))?;
+            serde_json::to_value(get_affordable_builds(me, strategy.build(game, graph)?))?
+        },
+        ResponseCode::InitialBuildRequest => serde_json::to_value(strategy.initial_build(game, graph)?)?,
+        ResponseCode::MoveBanditRequest => serde_json::to_value(strategy.move_bandit(game, graph)?)?,
+        ResponseCode::ForceDiscardRequest => serde_json::to_value(strategy.discard(game, graph)?)?,
+        _ => return Err(ClientError::Protocol(format!("{:?} is not a request", request))),
+    };
+    Ok(commands)
+}
 
-    let potential_villages = board.get_potential_village_nodes(game.me().unwrap());
-    println!("I have potential villages: {:?}", potential_villages);
-    if potential_villages.len() != 0 {
-        let random_village = potential_villages.choose(&mut rand::thread_rng()).unwrap();
-        let build_village = BuildCommand {
-            structure: String::from("village"),
-            location: (*random_village).key.clone()
-        };
-        let commands = vec!(&build_village);
-        return send_client_response(&stream, &mut buf_stream, serde_json::to_value(commands).unwrap())
-    }
+// Drop the builds the server would reject because we run out of resources, builds are paid for
+// in the order they are sent
+fn get_affordable_builds(me: &Player, builds: Vec<BuildCommand>) -> Vec<BuildCommand> {
+    let mut player = me.clone();
+    builds.into_iter().filter(|build| {
+        match player.resources_after(build.structure) {
+            Some(resources) => {
+                player.resources = resources;
+                true
+            },
+            None => {
+                warn!("Not sending {:?} at {}, we cannot afford it", build.structure, build.location);
+                false
+            }
+        }
+    }).collect()
+}
 
-    let potential_streets = board.get_potential_street_edges(game.me().unwrap());
-    println!("I have potential streets: {:?}", potential_streets);
-    if potential_streets.len() == 0 {
-        let commands: Vec<BuildCommand> = Vec::new();
-        return send_client_response(&stream, &mut buf_stream, serde_json::to_value(commands).unwrap())
-    }
-    let random_street = potential_streets.choose(&mut rand::thread_rng()).unwrap();
-    let build_street = BuildCommand {
-        structure: String::from("street"),
-        location: (*random_street).key.clone()
+fn send_client_response(stream: &TcpStream, buf_stream: &mut BufStream<&TcpStream>, response_data: serde_json::Value) -> Result<(), ClientError> {
+    let response = ClientResponse {
+        response: response_data
     };
-    let commands = vec!(&build_street);
-    send_client_response(&stream, &mut buf_stream, serde_json::to_value(commands).unwrap())
+    transmit(buf_stream, stream, &OutboundMessage::ClientResponse(response))
 }
 
-// Reads the TCP input and extracts a json object from it.
-// Returns Some(String) if the string is a properly formatted json object,
-// otherwise returns None
+// Reads the next line of TCP input, blocking until it is there.
+// Returns None when the server closed the connection or reading failed
 fn read_tcp_input(buf_stream: &mut BufStream<&TcpStream>) -> Option<String> {
     let mut buffer = String::new();
 
-    loop {
-        if let Ok(_buffer_size) = buf_stream.read_line(&mut buffer) {
-            return Some(buffer)
+    match buf_stream.read_line(&mut buffer) {
+        Ok(0) => None,
+        Ok(_buffer_size) => Some(buffer),
+        Err(e) => {
+            warn!("Could not read from the server: {}", e);
+            None
         }
     }
-    None
 }
 
 // Transmit a JSON object over the TCP connection and append a newline
-fn transmit<T: ?Sized>(buf_stream: &mut BufStream<&TcpStream>, stream: &TcpStream, value: &T) -> Result<(), &'static str> where T: Serialize {
-    serde_json::to_writer(stream, value).unwrap_or_default();
-    println!("transmiting: {}", serde_json::to_string_pretty(value).unwrap_or_default());
-    buf_stream.write(b"\r\n").unwrap_or_default(); // send a newline to indicate we are done
-    buf_stream.flush().unwrap_or_default();
+fn transmit<T: ?Sized + Serialize>(buf_stream: &mut BufStream<&TcpStream>, stream: &TcpStream, value: &T) -> Result<(), ClientError> {
+    serde_json::to_writer(stream, value)?;
+    debug!("transmiting: {}", serde_json::to_string_pretty(value)?);
+    buf_stream.write_all(b"\r\n")?; // send a newline to indicate we are done
+    buf_stream.flush()?;
     Ok(())
 }
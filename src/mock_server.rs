@@ -0,0 +1,326 @@
+use std::io::prelude::*;
+use std::io::{self, BufReader};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use log::info;
+
+use crate::engine::*;
+use crate::game::*;
+use crate::server::*;
+use crate::strategy::*;
+
+// A fake catan-server that speaks the same JSON-lines protocol as the real one. It plays a fixed
+// script against a single client and records everything the client sends back, so the tests can
+// check the networking of the client without the Java server.
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// how long we wait to be sure the client has nothing more to say
+const QUIET_TIMEOUT: Duration = Duration::from_millis(200);
+
+pub enum Step {
+    // wait for a join message of the client
+    ExpectJoin,
+    // send a response with code 1 that tells the client its id
+    SendIdAcknowledgment(i16),
+    // send a snapshot of a game in which the initial buildings are placed
+    SendGame,
+    // send the same snapshot with the game finished
+    SendFinishedGame,
+    // send a line as it is, e.g. one that is not valid json
+    SendRaw(&'static str),
+    // send one of the requests 100 - 104 and wait for the client-response
+    SendRequest(ResponseCode),
+    // reject the last client-response with an error that has the code and the additional info,
+    // the client should wait for the request to be asked again
+    SendError(i16, &'static str),
+    // close the connection and wait for the client to connect again
+    Disconnect,
+    // check that the client does not send anything else
+    ExpectNothing,
+}
+
+pub struct Recording {
+    pub joins: Vec<JoinMessage>,
+    pub client_responses: Vec<(ResponseCode, serde_json::Value)>,
+    pub failures: Vec<String>,
+}
+
+pub struct MockServer {
+    listener: TcpListener,
+    game: Game,
+}
+
+impl MockServer {
+    // listen on a free port of localhost
+    pub fn bind() -> io::Result<MockServer> {
+        let strategies: Vec<Box<dyn Strategy>> = (0..4).map(|_| {
+            Box::new(RandomStrategy::new(0)) as Box<dyn Strategy>
+        }).collect();
+        let mut local_game = LocalGame::new(strategies, 0);
+        local_game.place_initial_buildings();
+
+        Ok(MockServer {
+            listener: TcpListener::bind(("127.0.0.1", 0))?,
+            game: local_game.snapshot(0),
+        })
+    }
+
+    pub fn get_address(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Play the script against the clients that connect, stops at the first step that fails
+    pub fn play(&self, script: &[Step]) -> Recording {
+        let mut recording = Recording {
+            joins: Vec::new(),
+            client_responses: Vec::new(),
+            failures: Vec::new(),
+        };
+
+        let mut connection = None;
+        for step in script {
+            if connection.is_none() {
+                connection = match self.accept() {
+                    Ok(connection) => Some(connection),
+                    Err(e) => {
+                        recording.failures.push(format!("no client connected: {}", e));
+                        return recording
+                    }
+                };
+            }
+            let (stream, reader) = connection.as_mut().unwrap();
+
+            let result = match step {
+                Step::ExpectJoin => match read_message(reader) {
+                    Ok(OutboundMessage::Join(join)) => {
+                        info!("Client joined as {} with id {}", join.name, join.id);
+                        recording.joins.push(join);
+                        Ok(())
+                    },
+                    Ok(message) => Err(format!("expected a join, got {:?}", message)),
+                    Err(e) => Err(e),
+                },
+                Step::SendIdAcknowledgment(id) => {
+                    send_response(stream, ResponseCode::IdAcknowledgment, &id.to_string())
+                },
+                Step::SendGame => send_message(stream, &InboundMessage::Game(Box::new(self.game.clone()))),
+                Step::SendFinishedGame => {
+                    let mut game = self.game.clone();
+                    game.status = GameStatus::Finished;
+                    send_message(stream, &InboundMessage::Game(Box::new(game)))
+                },
+                Step::SendRaw(line) => {
+                    write_line(stream, line)
+                },
+                Step::SendRequest(code) => send_response(stream, *code, "").and_then(|_| {
+                    read_client_response(reader, *code, &mut recording)
+                }),
+                Step::SendError(code, additional_info) => send_error(stream, *code, additional_info),
+                Step::Disconnect => {
+                    connection = None;
+                    Ok(())
+                },
+                Step::ExpectNothing => expect_nothing(reader),
+            };
+
+            if let Err(e) = result {
+                recording.failures.push(e);
+                return recording
+            }
+        }
+        recording
+    }
+
+    // wait for a client to connect, gives up after the read timeout
+    fn accept(&self) -> io::Result<(TcpStream, BufReader<TcpStream>)> {
+        self.listener.set_nonblocking(true)?;
+        let start = Instant::now();
+        let stream = loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock && start.elapsed() < READ_TIMEOUT => {
+                    thread::sleep(Duration::from_millis(10));
+                },
+                Err(e) => return Err(e),
+            }
+        };
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let reader = BufReader::new(stream.try_clone()?);
+        Ok((stream, reader))
+    }
+}
+
+// read the next message of the client, empty lines are skipped
+fn read_message(reader: &mut BufReader<TcpStream>) -> Result<OutboundMessage, String> {
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Err(String::from("the client disconnected while we expected a message")),
+            Ok(_) => {},
+            Err(e) => return Err(format!("did not receive a message: {}", e)),
+        }
+        if line.trim().is_empty() {
+            continue
+        }
+        return serde_json::from_str(&line).map_err(|e| format!("client sent invalid message {}: {}", line.trim(), e))
+    }
+}
+
+fn expect_nothing(reader: &mut BufReader<TcpStream>) -> Result<(), String> {
+    reader.get_ref().set_read_timeout(Some(QUIET_TIMEOUT)).map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    let result = match reader.read_line(&mut line) {
+        Ok(0) => Ok(()),
+        Ok(_) => Err(format!("client sent an unexpected message {}", line.trim())),
+        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => Ok(()),
+        Err(e) => Err(e.to_string()),
+    };
+    reader.get_ref().set_read_timeout(Some(READ_TIMEOUT)).map_err(|e| e.to_string())?;
+    result
+}
+
+fn read_client_response(reader: &mut BufReader<TcpStream>, code: ResponseCode, recording: &mut Recording) -> Result<(), String> {
+    match read_message(reader)? {
+        OutboundMessage::ClientResponse(client_response) => {
+            info!("Client responded to {:?} with {}", code, client_response.response);
+            recording.client_responses.push((code, client_response.response));
+            Ok(())
+        },
+        message => Err(format!("expected a client-response to {:?}, got {:?}", code, message)),
+    }
+}
+
+fn send_response(stream: &mut TcpStream, code: ResponseCode, additional_info: &str) -> Result<(), String> {
+    let response = ServerResponse {
+        code: code as i16,
+        title: format!("{:?}", code),
+        description: String::new(),
+        additional_info: String::from(additional_info),
+        is_error: false,
+    };
+    send_message(stream, &InboundMessage::Response(response))
+}
+
+fn send_error(stream: &mut TcpStream, code: i16, additional_info: &str) -> Result<(), String> {
+    let response = ServerResponse {
+        code,
+        title: String::from("Error"),
+        description: String::from("the answer was rejected"),
+        additional_info: String::from(additional_info),
+        is_error: true,
+    };
+    send_message(stream, &InboundMessage::Response(response))
+}
+
+fn send_message(stream: &mut TcpStream, message: &InboundMessage) -> Result<(), String> {
+    write_line(stream, &serde_json::to_string(message).unwrap())
+}
+
+fn write_line(stream: &mut TcpStream, line: &str) -> Result<(), String> {
+    writeln!(stream, "{}", line).and_then(|_| stream.flush()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::run_client;
+
+    // Play the script on a mock server and run the client against it, with the reconnect loop of
+    // main. The client connects again after every disconnect, and gives up once the server is gone.
+    fn run(script: Vec<Step>) -> (Recording, io::Result<()>) {
+        let server = MockServer::bind().unwrap();
+        let address = server.get_address().unwrap();
+        let handle = thread::spawn(move || server.play(&script));
+
+        let args: Vec<String> = vec!("--host", "127.0.0.1", "--port", &address.port().to_string(), "--name", "tester",
+            "--strategy", "random", "--reconnect", "2")
+            .into_iter().map(String::from).collect();
+        let config = Config::from_args(&args).unwrap();
+        let result = run_client(&config, 0);
+        (handle.join().unwrap(), result)
+    }
+
+    fn get_answered(recording: &Recording) -> Vec<ResponseCode> {
+        recording.client_responses.iter().map(|(code, _)| *code).collect()
+    }
+
+    #[test]
+    fn rejoins_with_the_acknowledged_id() {
+        let (recording, result) = run(vec!(
+            Step::ExpectJoin,
+            Step::SendIdAcknowledgment(0),
+            Step::SendGame,
+            Step::Disconnect,
+            Step::ExpectJoin,
+            Step::SendIdAcknowledgment(0),
+            Step::SendRequest(ResponseCode::BuildRequest),
+        ));
+        assert!(recording.failures.is_empty(), "{:?}", recording.failures);
+        assert_eq!(recording.joins.iter().map(|join| join.id).collect::<Vec<i16>>(), vec!(-1, 0));
+        assert_eq!(get_answered(&recording), vec!(ResponseCode::BuildRequest));
+        // the server is gone after the script, the client tries once more and stops
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionRefused);
+    }
+
+    #[test]
+    fn joins_a_new_game_after_a_finished_one() {
+        let (recording, _) = run(vec!(
+            Step::ExpectJoin,
+            Step::SendIdAcknowledgment(2),
+            Step::SendFinishedGame,
+            Step::Disconnect,
+            Step::ExpectJoin,
+        ));
+        assert!(recording.failures.is_empty(), "{:?}", recording.failures);
+        assert_eq!(recording.joins.iter().map(|join| join.id).collect::<Vec<i16>>(), vec!(-1, -1));
+    }
+
+    #[test]
+    fn answers_every_request_once() {
+        let (recording, _) = run(vec!(
+            Step::ExpectJoin,
+            Step::SendIdAcknowledgment(0),
+            Step::SendGame,
+            Step::SendRequest(ResponseCode::InitialBuildRequest),
+            Step::SendRequest(ResponseCode::TradeRequest),
+            Step::SendRequest(ResponseCode::BuildRequest),
+            Step::SendRequest(ResponseCode::MoveBanditRequest),
+            // the client does not know the error codes of the server, any code will do
+            Step::SendError(999, "[3,3]"),
+            Step::ExpectNothing,
+            Step::SendRequest(ResponseCode::MoveBanditRequest),
+            Step::SendRequest(ResponseCode::ForceDiscardRequest),
+            Step::ExpectNothing,
+        ));
+        assert!(recording.failures.is_empty(), "{:?}", recording.failures);
+        assert_eq!(get_answered(&recording), vec!(
+            ResponseCode::InitialBuildRequest,
+            ResponseCode::TradeRequest,
+            ResponseCode::BuildRequest,
+            ResponseCode::MoveBanditRequest,
+            ResponseCode::MoveBanditRequest,
+            ResponseCode::ForceDiscardRequest,
+        ));
+    }
+
+    #[test]
+    fn survives_invalid_messages() {
+        let (recording, _) = run(vec!(
+            Step::ExpectJoin,
+            Step::SendIdAcknowledgment(0),
+            Step::SendGame,
+            Step::SendRaw("this is not json"),
+            Step::SendRaw(r#"{"model": "weather", "attributes": {"sunny": true}}"#),
+            Step::SendRaw(r#"{"model": "weather", "attributes": {"sunny": false}}"#),
+            Step::SendRequest(ResponseCode::InitialBuildRequest),
+            Step::ExpectNothing,
+        ));
+        assert!(recording.failures.is_empty(), "{:?}", recording.failures);
+        assert_eq!(get_answered(&recording), vec!(ResponseCode::InitialBuildRequest));
+        // the game is still known, without it the answer would be empty
+        assert_ne!(recording.client_responses[0].1, serde_json::json!([]));
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use log::debug;
+
+use crate::board::*;
+use crate::game::*;
+use crate::graph::*;
+use crate::history::*;
+
+// The server only tells us our own resources, the hands of the other players are reconstructed
+// from the public events: production after dice throws, builds, trades, discards and steals.
+
+const INITIAL_PLACEMENTS: usize = 2;
+
+// What we know about the hand of a player: lower bounds per resource plus a number of cards of
+// which we do not know the type
+#[derive(Clone, Default, Debug)]
+pub struct HandEstimate {
+    known: HashMap<ResourceKind, usize>,
+    unknown: usize,
+    initial_placements: usize,
+}
+
+impl HandEstimate {
+    pub fn get_total(&self) -> usize {
+        self.known.values().sum::<usize>() + self.unknown
+    }
+
+    pub fn get_known(&self, resource: ResourceKind) -> usize {
+        self.known.get(&resource).cloned().unwrap_or(0)
+    }
+
+    // the expected number of cards of a resource, an unknown card is equally likely to be anything
+    pub fn get_expected(&self, resource: ResourceKind) -> f64 {
+        self.get_known(resource) as f64 + self.unknown as f64 / ResourceKind::ALL.len() as f64
+    }
+
+    // the chance that a random card from the hand is of the resource
+    pub fn get_probability(&self, resource: ResourceKind) -> f64 {
+        match self.get_total() {
+            0 => 0.0,
+            total => self.get_expected(resource) / total as f64,
+        }
+    }
+
+    fn add(&mut self, resource: ResourceKind, amount: usize) {
+        *self.known.entry(resource).or_insert(0) += amount;
+    }
+
+    // the player gave away cards of a known type, any we did not know about were unknown cards
+    fn pay(&mut self, resource: ResourceKind, amount: usize) {
+        let known = self.known.entry(resource).or_insert(0);
+        let from_known = amount.min(*known);
+        *known -= from_known;
+        self.unknown -= (amount - from_known).min(self.unknown);
+    }
+
+    // the player lost every card of the resource. Some of the unknown cards may have been of it as
+    // well, but we cannot tell how many, so they stay unknown.
+    fn lose_all(&mut self, resource: ResourceKind) {
+        self.known.insert(resource, 0);
+    }
+
+    // the player lost a card of which we do not know the type
+    fn lose_unknown(&mut self) {
+        let total = self.get_total();
+        if total == 0 {
+            return
+        }
+        // it could have been any of the known cards, so every lower bound drops by one
+        for amount in self.known.values_mut() {
+            *amount = amount.saturating_sub(1);
+        }
+        self.unknown = total - 1 - self.known.values().sum::<usize>();
+    }
+}
+
+#[derive(Default)]
+pub struct OpponentModel {
+    hands: HashMap<usize, HandEstimate>,
+    history: GameHistory,
+    // What the tiles produced on the board of the previous update. Dice throws are counted on it,
+    // so buildings that were built after a throw cannot get resources from it.
+    previous_production: Vec<Production>,
+}
+
+// the resources a building gets from a tile next to it when the number of the tile is thrown
+struct Production {
+    tile_key: String,
+    number: u32,
+    player: usize,
+    resource: ResourceKind,
+    amount: usize,
+}
+
+impl OpponentModel {
+    pub fn new() -> OpponentModel {
+        OpponentModel::default()
+    }
+
+    // Apply the events of the snapshot that we have not seen yet
+    pub fn update(&mut self, game: &Game, graph: &BoardGraph) {
+        let entries: Vec<HistoryEntry> = self.history.update(game).to_vec();
+        for HistoryEntry { move_count, event } in entries {
+            debug!("Move {}: {:?}", move_count, event);
+            match event {
+                // the last throw of the snapshot may belong to a later event, so a throw without
+                // a number is left out
+                GameEvent::DiceThrow { number: Some(number), .. } => self.produce(graph, number),
+                GameEvent::DiceThrow { number: None, .. } => {},
+                GameEvent::Build { player, structures } => self.build(graph, player, &structures),
+                GameEvent::Trade { player, given, received } => {
+                    let hand = self.hands.entry(player).or_default();
+                    for resource in given {
+                        hand.pay(resource, 1);
+                    }
+                    if let Some(resource) = received {
+                        hand.add(resource, 1);
+                    }
+                },
+                GameEvent::Discard { player, resources } => {
+                    let hand = self.hands.entry(player).or_default();
+                    for resource in resources {
+                        hand.pay(resource, 1);
+                    }
+                },
+                GameEvent::Steal { player, victim } => {
+                    self.hands.entry(player).or_default().unknown += 1;
+                    if let Some(victim) = victim {
+                        self.hands.entry(victim).or_default().lose_unknown();
+                    }
+                },
+                GameEvent::BuyDevelopmentCard { player } => {
+                    let hand = self.hands.entry(player).or_default();
+                    for (resource, amount) in DEVELOPMENT_CARD_COST.iter() {
+                        hand.pay(*resource, *amount);
+                    }
+                },
+                GameEvent::PlayDevelopmentCard { player, resources, monopoly, .. } => {
+                    // a monopoly takes everything of the resource from the others
+                    if let Some(resource) = monopoly {
+                        for (_, hand) in self.hands.iter_mut().filter(|(other, _)| **other != player) {
+                            hand.lose_all(resource);
+                        }
+                    }
+                    let hand = self.hands.entry(player).or_default();
+                    for resource in resources {
+                        hand.add(resource, 1);
+                    }
+                },
+                GameEvent::MoveBandit { .. } | GameEvent::Other { .. } => {},
+            }
+        }
+        self.previous_production = get_production(graph);
+    }
+
+    pub fn get_hand(&self, player: usize) -> Option<&HandEstimate> {
+        self.hands.get(&player)
+    }
+
+    // The candidate from which we are most likely to steal the resource
+    pub fn get_best_victim(&self, resource: ResourceKind, candidates: &[usize]) -> Option<usize> {
+        candidates.iter().cloned()
+            .filter(|player| self.get_hand(*player).is_some_and(|hand| hand.get_total() > 0))
+            .max_by(|a, b| {
+                let probability = |player: &usize| self.get_hand(*player).map_or(0.0, |hand| hand.get_probability(resource));
+                probability(a).total_cmp(&probability(b))
+            })
+    }
+
+    // every building next to a tile with the thrown number gets its resource, unless the bandit is
+    // on the tile. The bandit may have moved since the previous board, so the tiles it is on in
+    // both boards are skipped.
+    fn produce(&mut self, graph: &BoardGraph, number: u8) {
+        let bandits = graph.get_board().get_bandits();
+        for production in self.previous_production.iter().filter(|production| production.number == number as u32) {
+            if bandits.iter().any(|bandit| bandit.tile_key == production.tile_key) {
+                continue
+            }
+            self.hands.entry(production.player).or_default().add(production.resource, production.amount);
+        }
+    }
+
+    // the first villages and their streets are free, everything after that is paid for
+    fn build(&mut self, graph: &BoardGraph, player: usize, structures: &[StructureKind]) {
+        let hand = self.hands.entry(player).or_default();
+        if structures.contains(&StructureKind::Village) && hand.initial_placements < INITIAL_PLACEMENTS {
+            hand.initial_placements += 1;
+            if hand.initial_placements == INITIAL_PLACEMENTS {
+                // the second village gives the resources around it, we do not know which of the
+                // villages that was, so the player gets at least as many unknown cards as the
+                // village next to the fewest resources
+                hand.unknown += graph.get_nodes().iter()
+                    .filter(|node| node.player == Some(player))
+                    .map(|node| graph.get_tiles_surrounding_node(node).into_iter().filter(|tile| tile.resource_type.resource().is_some()).count())
+                    .min()
+                    .unwrap_or(0);
+            }
+            return
+        }
+        for structure in structures {
+            for (resource, amount) in structure.get_cost() {
+                hand.pay(resource, amount);
+            }
+        }
+    }
+}
+
+// what every tile without the bandit gives to the buildings around it
+fn get_production(graph: &BoardGraph) -> Vec<Production> {
+    let bandits = graph.get_board().get_bandits();
+    let mut production = Vec::new();
+    for tile in graph.get_tiles() {
+        let resource = match tile.resource_type.resource() {
+            Some(resource) if !bandits.iter().any(|bandit| bandit.tile_key == tile.key) => resource,
+            _ => continue,
+        };
+        for node in graph.get_nodes_surrounding_tile(tile) {
+            if let Some(player) = node.player {
+                let amount = if node.structure == Some(StructureKind::City) { 2 } else { 1 };
+                production.push(Production { tile_key: tile.key.clone(), number: tile.number, player, resource, amount });
+            }
+        }
+    }
+    production
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::*;
+
+    fn get_event(move_count: u32, player: usize, attributes: serde_json::Value) -> ServerInputEvent {
+        let mut attributes = attributes;
+        attributes["move_count"] = serde_json::json!(move_count);
+        attributes["player"] = serde_json::json!(player);
+        ServerInputEvent { model: String::from("event"), attributes }
+    }
+
+    fn update(model: &mut OpponentModel, events: Vec<ServerInputEvent>, last_dice_throw: Option<u8>) {
+        let mut game = Game::new(vec!(get_player(0), get_player(1)), two_player_board());
+        game.events = Some(events);
+        game.last_dice_throw = last_dice_throw;
+        let graph = BoardGraph::new(two_player_board());
+        model.update(&game, &graph);
+    }
+
+    #[test]
+    fn applies_a_monopoly_that_took_nothing() {
+        let mut model = OpponentModel::new();
+        update(&mut model, vec!(
+            get_event(1, 1, serde_json::json!({"event_type": "trade", "resources": ["grain", "grain", "grain", "grain", "wool"]})),
+            // nobody had to give any wool, so there are no resources to read it from
+            get_event(2, 0, serde_json::json!({"event_type": "play_development_card", "development_card": "monopoly", "monopoly": "wool"})),
+        ), None);
+        assert_eq!(model.get_hand(1).unwrap().get_known(ResourceKind::Wool), 0);
+    }
+
+    #[test]
+    fn skips_dice_throws_without_a_number() {
+        let mut model = OpponentModel::new();
+        update(&mut model, Vec::new(), None);
+        // the last throw of the snapshot is a 6, but it may not be the one of this event
+        update(&mut model, vec!(
+            get_event(1, 0, serde_json::json!({"event_type": "throw_dice", "message": "player 0 threw the dice"})),
+        ), Some(6));
+        assert_eq!(model.get_hand(0).map_or(0, |hand| hand.get_total()), 0);
+        // the village on n0 touches three grain tiles with a 6
+        update(&mut model, vec!(
+            get_event(2, 0, serde_json::json!({"event_type": "throw_dice", "message": "player 0 threw 6"})),
+        ), Some(6));
+        assert_eq!(model.get_hand(0).unwrap().get_known(ResourceKind::Grain), 3);
+    }
+}
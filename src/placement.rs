@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use crate::board::*;
+use crate::graph::*;
+
+// Scores the spots for the initial villages. A spot is worth the pips of the tiles around it, where
+// pips of resources that are rare on this board count for more, plus a bonus for every resource
+// we do not produce yet and for a port.
+
+const DIVERSITY_BONUS: f64 = 2.0;
+const GENERIC_PORT_BONUS: f64 = 1.5;
+// a resource port is worth more the more of its resource we produce
+const RESOURCE_PORT_BONUS_PER_PIP: f64 = 0.5;
+// how much the best second spot that is likely left counts when choosing the first one
+const SECOND_PLACEMENT_WEIGHT: f64 = 0.5;
+
+pub struct PlacementEvaluator<'a> {
+    graph: &'a BoardGraph,
+    scarcity: HashMap<ResourceKind, f64>,
+}
+
+impl<'a> PlacementEvaluator<'a> {
+    pub fn new(graph: &'a BoardGraph) -> PlacementEvaluator<'a> {
+        // a resource with half of the average pips on the board is worth twice as much
+        let mut pips: HashMap<ResourceKind, f64> = ResourceKind::ALL.iter().map(|resource| (*resource, 0.0)).collect();
+        for tile in graph.get_tiles() {
+            if let Some(resource) = tile.resource_type.resource() {
+                *pips.entry(resource).or_insert(0.0) += tile.get_pips() as f64;
+            }
+        }
+        let average = pips.values().sum::<f64>() / pips.len() as f64;
+        let scarcity = pips.into_iter().map(|(resource, pips)| (resource, average / pips.max(1.0))).collect();
+
+        PlacementEvaluator { graph, scarcity }
+    }
+
+    // The value of a village on the node for a player that already produces the given resources
+    pub fn score_node(&self, node: &Node, produced: &[ResourceKind]) -> f64 {
+        let mut score = 0.0;
+        let mut new_resources: Vec<ResourceKind> = Vec::new();
+        let ports = self.graph.get_ports_of_node(node);
+        if ports.iter().any(|port| port.kind == PortKind::Generic) {
+            score += GENERIC_PORT_BONUS;
+        }
+        for tile in self.graph.get_tiles_surrounding_node(node) {
+            if let Some(resource) = tile.resource_type.resource() {
+                if ports.iter().any(|port| port.kind == PortKind::Resource(resource)) {
+                    score += RESOURCE_PORT_BONUS_PER_PIP * tile.get_pips() as f64;
+                }
+                score += tile.get_pips() as f64 * self.scarcity[&resource];
+                if tile.get_pips() > 0 && !produced.contains(&resource) && !new_resources.contains(&resource) {
+                    new_resources.push(resource);
+                }
+            }
+        }
+        score + DIVERSITY_BONUS * new_resources.len() as f64
+    }
+
+    // The best village and the street next to it for the player. The first village also counts
+    // the best second spot that is probably still free when the snake draft comes back to us, the
+    // second one is chosen to complement the first.
+    pub fn get_best_placement(&self, player: &Player, player_count: usize) -> Option<(&'a Node, &'a Edge)> {
+        let own_nodes: Vec<&Node> = self.graph.get_nodes().iter().cloned().filter(|node| node.player == Some(player.id)).collect();
+        let produced = self.get_produced_resources(&own_nodes);
+        let free_nodes: Vec<&'a Node> = self.graph.get_nodes().iter().cloned().filter(|node| self.graph.is_free_village_spot(node)).collect();
+
+        // every player before us in the draft takes a spot, and after our first pick all players
+        // after us take two
+        let placed_villages = self.graph.get_nodes().iter().filter(|node| node.structure.is_some()).count();
+        let picks_between = 2 * player_count.saturating_sub(placed_villages + 1);
+
+        // the other players are expected to take the spots with the highest score of their own
+        let mut ranked = free_nodes.clone();
+        let base_scores: HashMap<&str, f64> = free_nodes.iter().map(|node| (node.key.as_str(), self.score_node(node, &[]))).collect();
+        ranked.sort_by(|a, b| base_scores[b.key.as_str()].total_cmp(&base_scores[a.key.as_str()]));
+
+        let scores: Vec<(&'a Node, f64)> = free_nodes.iter().map(|node| {
+            let mut score = self.score_node(node, &produced);
+            if own_nodes.is_empty() {
+                score += SECOND_PLACEMENT_WEIGHT * self.get_expected_second_score(node, &ranked, picks_between);
+            }
+            (*node, score)
+        }).collect();
+        let village = scores.into_iter().max_by(|(_, a), (_, b)| a.total_cmp(b)).map(|(node, _)| node)?;
+        let street = self.get_best_street(village, &produced)?;
+        Some((village, street))
+    }
+
+    // The candidate spot that adds the most to what the player already produces
+    pub fn get_best_village<'b>(&self, player: &Player, candidates: Vec<&'b Node>) -> Option<&'b Node> {
+        let own_nodes: Vec<&Node> = self.graph.get_nodes().iter().cloned().filter(|node| node.player == Some(player.id)).collect();
+        let produced = self.get_produced_resources(&own_nodes);
+        candidates.into_iter().max_by(|a, b| self.score_node(a, &produced).total_cmp(&self.score_node(b, &produced)))
+    }
+
+    // The free street next to the village that leads to the best spot for the next village
+    pub fn get_best_street(&self, village: &Node, produced: &[ResourceKind]) -> Option<&'a Edge> {
+        self.graph.get_edges_surrounding_node(village).into_iter()
+            .filter(|edge| edge.player.is_none())
+            .max_by(|a, b| self.get_expansion_score(village, a, produced).total_cmp(&self.get_expansion_score(village, b, produced)))
+    }
+
+    // the best free spot two streets away from the village, through the given street
+    fn get_expansion_score(&self, village: &Node, street: &Edge, produced: &[ResourceKind]) -> f64 {
+        self.graph.get_nodes_surrounding_edge(street).into_iter()
+            .filter(|node| node.key != village.key)
+            .flat_map(|node| self.graph.get_nodes_surrounding_node(node))
+            .filter(|node| node.key != village.key && self.graph.is_free_village_spot(node))
+            .map(|node| self.score_node(node, produced))
+            .fold(0.0, f64::max)
+    }
+
+    // the score of the best spot that is left for our second village, when the other players take
+    // the best ranked spots in between
+    fn get_expected_second_score(&self, first: &Node, ranked: &[&Node], picks_between: usize) -> f64 {
+        let blocked: Vec<&Node> = self.graph.get_nodes_surrounding_node(first);
+        let produced = self.get_produced_resources(&[first]);
+        ranked.iter().cloned()
+            .filter(|node| node.key != first.key && !blocked.iter().any(|blocked| blocked.key == node.key))
+            .skip(picks_between)
+            .map(|node| self.score_node(node, &produced))
+            .fold(0.0, f64::max)
+    }
+
+    fn get_produced_resources(&self, nodes: &[&Node]) -> Vec<ResourceKind> {
+        let mut produced: Vec<ResourceKind> = nodes.iter()
+            .flat_map(|node| self.graph.get_tiles_surrounding_node(node))
+            .filter(|tile| tile.get_pips() > 0)
+            .filter_map(|tile| tile.resource_type.resource())
+            .collect();
+        produced.sort();
+        produced.dedup();
+        produced
+    }
+}
@@ -0,0 +1,123 @@
+use crate::board::*;
+use crate::graph::*;
+
+// Counts the victory points of the players and estimates how far they are from winning.
+
+pub const WINNING_POINTS: u32 = 10;
+const LONGEST_ROAD_POINTS: u32 = 2;
+// a road needs at least this many streets to count as the longest road
+const MIN_LONGEST_ROAD: usize = 5;
+const LARGEST_ARMY_POINTS: u32 = 2;
+pub const MIN_LARGEST_ARMY: usize = 3;
+// roughly what a point costs, a village with the streets to reach it or a city
+const CARDS_PER_POINT: f64 = 5.0;
+
+// Who holds the longest road and the largest army. Neither can be told from a single snapshot, a
+// tie goes to whoever got there first, so they are carried from one snapshot to the next.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Holders {
+    pub longest_road: Option<usize>,
+    pub largest_army: Option<usize>,
+}
+
+pub struct Scoreboard<'a> {
+    graph: &'a BoardGraph,
+    players: Vec<&'a Player>,
+    holders: Holders,
+}
+
+impl<'a> Scoreboard<'a> {
+    // The scores of the players, given who held the longest road and the largest army before
+    pub fn new(graph: &'a BoardGraph, players: Vec<&'a Player>, previous: Holders) -> Scoreboard<'a> {
+        let roads: Vec<(usize, usize)> = players.iter().map(|player| (player.id, graph.get_longest_road(player))).collect();
+        let armies: Vec<(usize, usize)> = players.iter().map(|player| (player.id, player.played_knights)).collect();
+        let holders = Holders {
+            longest_road: get_holder(previous.longest_road, &roads, MIN_LONGEST_ROAD),
+            largest_army: get_holder(previous.largest_army, &armies, MIN_LARGEST_ARMY),
+        };
+        Scoreboard { graph, players, holders }
+    }
+
+    pub fn get_holders(&self) -> Holders {
+        self.holders
+    }
+
+    pub fn victory_points(&self, player: &Player) -> u32 {
+        let villages = self.graph.get_villages_from_player(player).len() as u32;
+        let cities = self.graph.get_cities_from_player(player).len() as u32;
+        let longest_road = if self.get_longest_road_holder() == Some(player.id) { LONGEST_ROAD_POINTS } else { 0 };
+        let largest_army = if self.get_largest_army_holder() == Some(player.id) { LARGEST_ARMY_POINTS } else { 0 };
+        // we only know the victory point cards of our own player
+        let cards = player.get_development_card_count(DevelopmentCardKind::VictoryPoint) as u32;
+        villages + 2 * cities + longest_road + largest_army + cards
+    }
+
+    // The player with the longest road of at least five streets
+    pub fn get_longest_road_holder(&self) -> Option<usize> {
+        self.holders.longest_road
+    }
+
+    // The player that played the most knights, at least three
+    pub fn get_largest_army_holder(&self) -> Option<usize> {
+        self.holders.largest_army
+    }
+
+    // The candidate with the most points, ties go to the one that is expected to win first
+    pub fn leader(&self, candidates: &[usize]) -> Option<usize> {
+        self.players.iter().filter(|player| candidates.contains(&player.id)).max_by(|a, b| {
+            self.victory_points(a).cmp(&self.victory_points(b))
+                .then(self.turns_to_win(b).total_cmp(&self.turns_to_win(a)))
+        }).map(|player| player.id)
+    }
+
+    // A rough number of turns the player needs to win, if it keeps spending what its buildings
+    // produce on new points
+    pub fn turns_to_win(&self, player: &Player) -> f64 {
+        let needed = WINNING_POINTS.saturating_sub(self.victory_points(player));
+        if needed == 0 {
+            return 0.0
+        }
+        // every pip is one of the 36 dice combinations, a city produces twice
+        let production: f64 = self.graph.get_nodes().iter().filter(|node| node.player == Some(player.id)).map(|node| {
+            let amount = if node.structure == Some(StructureKind::City) { 2.0 } else { 1.0 };
+            amount * self.graph.get_pips_of_node(node) as f64 / 36.0
+        }).sum();
+        needed as f64 * CARDS_PER_POINT / production
+    }
+}
+
+// The player with the most, at least the minimum. The holder keeps it until someone has strictly
+// more, when the most is tied between others nobody gets it.
+fn get_holder(holder: Option<usize>, values: &[(usize, usize)], minimum: usize) -> Option<usize> {
+    let most = values.iter().map(|(_, value)| *value).max().filter(|most| *most >= minimum)?;
+    let leaders: Vec<usize> = values.iter().filter(|(_, value)| *value == most).map(|(player, _)| *player).collect();
+    match leaders.as_slice() {
+        _ if holder.is_some_and(|holder| leaders.contains(&holder)) => holder,
+        [leader] => Some(*leader),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holder_keeps_it_on_a_tie() {
+        assert_eq!(get_holder(Some(0), &[(0, 5), (1, 5)], MIN_LONGEST_ROAD), Some(0));
+        assert_eq!(get_holder(Some(1), &[(0, 5), (1, 5)], MIN_LONGEST_ROAD), Some(1));
+    }
+
+    #[test]
+    fn holder_changes_on_strictly_more() {
+        assert_eq!(get_holder(Some(0), &[(0, 5), (1, 6)], MIN_LONGEST_ROAD), Some(1));
+        assert_eq!(get_holder(None, &[(0, 5), (1, 4)], MIN_LONGEST_ROAD), Some(0));
+    }
+
+    #[test]
+    fn nobody_holds_a_tie_or_too_little() {
+        assert_eq!(get_holder(None, &[(0, 5), (1, 5)], MIN_LONGEST_ROAD), None);
+        assert_eq!(get_holder(Some(2), &[(0, 5), (1, 5), (2, 3)], MIN_LONGEST_ROAD), None);
+        assert_eq!(get_holder(None, &[(0, 2), (1, 1)], MIN_LARGEST_ARMY), None);
+    }
+}
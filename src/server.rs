@@ -1,4 +1,10 @@
 use serde::{Deserialize, Serialize};
+use num_derive::FromPrimitive;
+
+use crate::game::*;
+
+// Every line sent over the connection is a json object with the name of a model and its
+// attributes, e.g. {"model": "join", "attributes": {"id": -1, "name": "Luke Skywalker"}}
 
 #[derive(Serialize, Deserialize)]
 pub struct ServerInput {
@@ -6,6 +12,28 @@ pub struct ServerInput {
     pub attributes: serde_json::Value,
 }
 
+// The messages the server sends to the clients
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "model", content = "attributes", rename_all = "kebab-case")]
+pub enum InboundMessage {
+    Game(Box<Game>),
+    Response(ServerResponse),
+    // a chat message for everyone in the game
+    Message(ServerMessage),
+}
+
+impl InboundMessage {
+    pub const MODELS: [&'static str; 3] = ["game", "response", "message"];
+}
+
+// The messages a client sends to the server
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "model", content = "attributes", rename_all = "kebab-case")]
+pub enum OutboundMessage {
+    Join(JoinMessage),
+    ClientResponse(ClientResponse),
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ServerMessage {
     pub message: String,
@@ -20,13 +48,42 @@ pub struct ServerResponse {
     pub is_error: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct JoinMessage {
     pub id: i16,
     pub name: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct ClientResponse {
     pub response: serde_json::Value
 }
+
+// The codes of the responses of the server that the client knows: 0 and 1 are informational and
+// 100 - 104 ask the client for commands. The server has error codes as well, but the client does
+// not decode them, an error is recognized by is_error and described by its title and description.
+#[derive(FromPrimitive, Clone, Copy, PartialEq, Debug)]
+pub enum ResponseCode {
+    Ok = 0,
+    IdAcknowledgment = 1,
+    TradeRequest = 100,
+    BuildRequest = 101,
+    InitialBuildRequest = 102,
+    MoveBanditRequest = 103,
+    ForceDiscardRequest = 104,
+}
+
+impl ResponseCode {
+    pub fn is_request(&self) -> bool {
+        (*self as i16) >= 100
+    }
+}
+
+// The server rejected our answer to a request
+#[derive(Debug)]
+pub struct Rejection {
+    pub request: ResponseCode,
+    pub description: String,
+    // usually the location or the trade that was rejected
+    pub additional_info: String,
+}
@@ -0,0 +1,482 @@
+use log::debug;
+use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+
+use crate::bandit::*;
+use crate::board::*;
+use crate::commands::*;
+use crate::discard::*;
+use crate::error::*;
+use crate::game::*;
+use crate::graph::*;
+use crate::legal_moves::*;
+use crate::opponents::*;
+use crate::placement::*;
+use crate::scoring::*;
+use crate::server::*;
+use crate::trading::*;
+
+// A Strategy makes the decisions of a bot. Every request the server can send us has its own
+// method, which gets a read-only view of the game with the index over its board and returns the
+// commands to send back.
+// The strategy never touches the network, that is up to the caller.
+pub trait Strategy {
+    // Respond to a TradeRequest
+    fn trade(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<TradeCommand>, ClientError>;
+
+    // Respond to a BuildRequest
+    fn build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError>;
+
+    // Respond to an InitialBuildRequest
+    fn initial_build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError>;
+
+    // Respond to a MoveBanditRequest
+    fn move_bandit(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<MoveBanditCommand>, ClientError>;
+
+    // Respond to a ForceDiscardRequest, returns the resources to discard
+    fn discard(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<Resource>, ClientError>;
+
+    // Decide on a development card to play at the start of our turn, victory points are never
+    // played. The server has no request for this yet, only the local engine asks.
+    fn play_development_card(&mut self, _game: &Game, _graph: &BoardGraph) -> Result<Option<PlayDevelopmentCardCommand>, ClientError> {
+        Ok(None)
+    }
+
+    // Decide on the development cards to buy after building, like play_development_card only the
+    // local engine asks
+    fn buy_development_cards(&mut self, _game: &Game, _graph: &BoardGraph) -> Result<Vec<BuyDevelopmentCardCommand>, ClientError> {
+        Ok(Vec::new())
+    }
+
+    // Called when our answer to a request was rejected. When the server wants another answer it
+    // asks the same request again, so the strategy can try something else
+    fn rejected(&mut self, _game: &Game, _rejection: &Rejection) {}
+
+    // Called with every snapshot of the game, before the requests about it, so the strategy can
+    // follow what the other players do
+    fn observe(&mut self, _game: &Game, _graph: &BoardGraph) {}
+}
+
+// The names of all strategies that can be chosen from the command line
+pub const STRATEGY_NAMES: [&str; 2] = ["random", "heuristic"];
+
+// Create the strategy with the given name, seeded so games can be replayed
+pub fn create_strategy(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "random" => Some(Box::new(RandomStrategy::new(seed))),
+        "heuristic" => Some(Box::new(HeuristicStrategy::new(seed))),
+        _ => None,
+    }
+}
+
+// Makes random decisions, with a slight preference for villages over streets.
+pub struct RandomStrategy {
+    rng: StdRng,
+    // the answers the server rejected during the current move
+    rejected_locations: Vec<String>,
+    rejected_trades: Vec<TradeCommand>,
+    rejected_discards: Vec<Vec<Resource>>,
+    rejected_move_count: Option<u32>,
+    // our last answers to a trade and a discard request, a rejection does not repeat them
+    last_trades: Vec<TradeCommand>,
+    last_discard: Vec<Resource>,
+    opponents: OpponentModel,
+    // who held the longest road and largest army in the last snapshot
+    holders: Holders,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> RandomStrategy {
+        RandomStrategy {
+            rng: StdRng::seed_from_u64(seed),
+            rejected_locations: Vec::new(),
+            rejected_trades: Vec::new(),
+            rejected_discards: Vec::new(),
+            rejected_move_count: None,
+            last_trades: Vec::new(),
+            last_discard: Vec::new(),
+            opponents: OpponentModel::new(),
+            holders: Holders::default(),
+        }
+    }
+
+    fn is_rejected(&self, game: &Game, location: &str) -> bool {
+        self.rejected_move_count == game.move_count && self.rejected_locations.iter().any(|rejected| rejected == location)
+    }
+
+    fn is_rejected_trade(&self, game: &Game, trade: &TradeCommand) -> bool {
+        self.rejected_move_count == game.move_count && self.rejected_trades.contains(trade)
+    }
+
+    fn is_rejected_discard(&self, game: &Game, discard: &[Resource]) -> bool {
+        self.rejected_move_count == game.move_count && self.rejected_discards.iter().any(|rejected| rejected == discard)
+    }
+}
+
+impl Strategy for RandomStrategy {
+    // Make a random trade, if we can afford any
+    fn trade(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<TradeCommand>, ClientError> {
+        let me = get_me(game)?;
+        let trades: Vec<TradeCommand> = legal_moves(graph, me, ResponseCode::TradeRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Trade(trade) if !self.is_rejected_trade(game, &trade) => Some(trade),
+                _ => None,
+            }
+        }).collect();
+        self.last_trades = trades.into_iter().choose(&mut self.rng).into_iter().collect();
+        Ok(self.last_trades.clone())
+    }
+
+    // If a village can be upgraded, build a city on the village with the most pips. Otherwise build
+    // a village, otherwise try building a street, otherwise do nothing.
+    fn build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+        let me = get_me(game)?;
+
+        debug!("I have resources: {:?}", me.resources);
+        debug!("I still need {:?} for a village", me.missing_for(StructureKind::Village));
+
+        let builds: Vec<BuildCommand> = legal_moves(graph, me, ResponseCode::BuildRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Build(build) => Some(build),
+                _ => None,
+            }
+        }).collect();
+        debug!("I can build: {:?}", builds);
+
+        let (cities, builds): (Vec<BuildCommand>, Vec<BuildCommand>) = builds.into_iter()
+            .filter(|build| !self.is_rejected(game, &build.location))
+            .partition(|build| build.structure == StructureKind::City);
+        if let Some(best_city) = get_best_city(graph, cities) {
+            return Ok(vec!(best_city))
+        }
+
+        let (villages, streets): (Vec<BuildCommand>, Vec<BuildCommand>) = builds.into_iter()
+            .partition(|build| build.structure == StructureKind::Village);
+        if let Some(random_village) = villages.into_iter().choose(&mut self.rng) {
+            return Ok(vec!(random_village))
+        }
+        Ok(streets.into_iter().choose(&mut self.rng).into_iter().collect())
+    }
+
+    // Create a village and street at a random legal location
+    fn initial_build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+        let me = get_me(game)?;
+        let placements: Vec<Vec<BuildCommand>> = legal_moves(graph, me, ResponseCode::InitialBuildRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::InitialBuild(village, street) if !self.is_rejected(game, &village.location) => Some(vec!(village, street)),
+                _ => None,
+            }
+        }).collect();
+        placements.into_iter().choose(&mut self.rng).ok_or(ClientError::MissingState("there is no place left for a village"))
+    }
+
+    // Places the bandit next to the opponent we most likely steal a missing resource from, on a
+    // random tile otherwise.
+    fn move_bandit(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<MoveBanditCommand>, ClientError> {
+        let me = get_me(game)?;
+        let bandit_moves: Vec<MoveBanditCommand> = legal_moves(graph, me, ResponseCode::MoveBanditRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Bandit(bandit_move) if !self.is_rejected(game, &bandit_move.location) => Some(bandit_move),
+                _ => None,
+            }
+        }).collect();
+
+        let needed = me.missing_for(StructureKind::Village).into_iter().max_by_key(|resource| resource.value).map(|resource| resource.r#type);
+        let opponents: Vec<usize> = game.get_players().into_iter().map(|player| player.id).filter(|id| *id != me.id).collect();
+        let victim = needed.and_then(|needed| self.opponents.get_best_victim(needed, &opponents));
+        let targeted: Vec<MoveBanditCommand> = bandit_moves.iter().filter(|bandit_move| {
+            let owners: Vec<usize> = graph.get_tile_by_key(&bandit_move.location).into_iter()
+                .flat_map(|tile| graph.get_nodes_surrounding_tile(tile))
+                .filter_map(|node| node.player)
+                .collect();
+            victim.is_some_and(|victim| owners.contains(&victim)) && !owners.contains(&me.id)
+        }).cloned().collect();
+        debug!("Stealing from {:?} for {:?}", victim, needed);
+
+        let options = if targeted.is_empty() { bandit_moves } else { targeted };
+        Ok(options.into_iter().choose(&mut self.rng).into_iter().collect())
+    }
+
+    // Discards a random half of our resources.
+    fn discard(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<Resource>, ClientError> {
+        let me = get_me(game)?;
+        let discards: Vec<Vec<Resource>> = legal_moves(graph, me, ResponseCode::ForceDiscardRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Discard(resources) if !self.is_rejected_discard(game, &resources) => Some(resources),
+                _ => None,
+            }
+        }).collect();
+        self.last_discard = discards.into_iter().choose(&mut self.rng).unwrap_or_default();
+        Ok(self.last_discard.clone())
+    }
+
+    // Follow the hands of the other players and who holds the longest road and largest army
+    fn observe(&mut self, game: &Game, graph: &BoardGraph) {
+        self.opponents.update(game, graph);
+        self.holders = Scoreboard::new(graph, game.get_players(), self.holders).get_holders();
+    }
+
+    // Remember the rejected answer, so we pick another one for the rest of this move. We go by the
+    // request that was rejected, the error codes of the server are not known to us.
+    fn rejected(&mut self, game: &Game, rejection: &Rejection) {
+        debug!("{:?} was rejected: {}", rejection.request, rejection.description);
+        if self.rejected_move_count != game.move_count {
+            self.rejected_locations.clear();
+            self.rejected_trades.clear();
+            self.rejected_discards.clear();
+            self.rejected_move_count = game.move_count;
+        }
+        match rejection.request {
+            ResponseCode::TradeRequest => self.rejected_trades.extend(self.last_trades.iter().cloned()),
+            ResponseCode::ForceDiscardRequest => self.rejected_discards.push(self.last_discard.clone()),
+            // the location is in the additional info, if the server tells us which one it was
+            _ if !rejection.additional_info.is_empty() => self.rejected_locations.push(rejection.additional_info.clone()),
+            _ => {},
+        }
+    }
+}
+
+// Scores its options and picks the best one. Requests it has no heuristic for are left to a
+// RandomStrategy.
+pub struct HeuristicStrategy {
+    random: RandomStrategy,
+}
+
+// a monopoly is only worth playing when the others are expected to hold this many cards of it
+const MIN_MONOPOLY_CARDS: f64 = 4.0;
+
+impl HeuristicStrategy {
+    pub fn new(seed: u64) -> HeuristicStrategy {
+        HeuristicStrategy {
+            random: RandomStrategy::new(seed),
+        }
+    }
+
+    // the tile that blocks the leading opponent most, out of the tiles the bandit can move to
+    fn get_bandit_target<'a>(&self, game: &Game, graph: &'a BoardGraph, me: &Player) -> Option<BanditTarget<'a>> {
+        let locations: Vec<String> = legal_moves(graph, me, ResponseCode::MoveBanditRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Bandit(bandit_move) if !self.random.is_rejected(game, &bandit_move.location) => Some(bandit_move.location),
+                _ => None,
+            }
+        }).collect();
+        let opponents: Vec<usize> = game.get_players().into_iter().map(|player| player.id).filter(|id| *id != me.id).collect();
+        let leader = Scoreboard::new(graph, game.get_players(), self.random.holders).leader(&opponents);
+        get_best_bandit_target(graph, me.id, leader, &locations, &self.random.opponents)
+    }
+
+    // the resources a year of plenty should take, when two cards are enough for the next build
+    fn get_year_of_plenty(&self, graph: &BoardGraph, me: &Player) -> Option<Vec<ResourceKind>> {
+        let structure = BUILD_PRIORITY.iter().cloned().filter(|structure| has_location(graph, me, *structure)).find(|structure| {
+            let missing: usize = me.missing_for(*structure).iter().map(|resource| resource.value).sum();
+            missing > 0 && missing <= 2
+        })?;
+        let mut resources: Vec<ResourceKind> = me.missing_for(structure).into_iter()
+            .flat_map(|resource| std::iter::repeat_n(resource.r#type, resource.value))
+            .collect();
+        // a spare card goes to the resource we have the fewest of
+        if resources.len() < 2 {
+            resources.extend(ResourceKind::ALL.iter().cloned().min_by_key(|resource| me.get_resource_count(*resource)));
+        }
+        Some(resources)
+    }
+
+    // the resource of which the others are expected to hold the most
+    fn get_monopoly(&self, game: &Game, me: &Player) -> Option<ResourceKind> {
+        let expected = |resource: ResourceKind| -> f64 {
+            game.get_players().into_iter()
+                .filter(|player| player.id != me.id)
+                .filter_map(|player| self.random.opponents.get_hand(player.id))
+                .map(|hand| hand.get_expected(resource))
+                .sum()
+        };
+        ResourceKind::ALL.iter().cloned()
+            .max_by(|a, b| expected(*a).total_cmp(&expected(*b)))
+            .filter(|resource| expected(*resource) >= MIN_MONOPOLY_CARDS)
+    }
+}
+
+impl Strategy for HeuristicStrategy {
+    // Trade our surplus for the missing cards of the most valuable structure we have room for. When
+    // that one is already affordable there is nothing to trade for.
+    fn trade(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<TradeCommand>, ClientError> {
+        let me = get_me(game)?;
+        let rates = TradeRates::for_player(graph.get_board(), me);
+        self.random.last_trades = Vec::new();
+        for structure in BUILD_PRIORITY.iter().cloned() {
+            if !has_location(graph, me, structure) {
+                continue
+            }
+            match plan_trades(me, &rates, structure) {
+                Some(trades) if !trades.iter().any(|trade| self.random.is_rejected_trade(game, trade)) => {
+                    debug!("Trading {:?} for a {:?}", trades, structure);
+                    self.random.last_trades = trades.clone();
+                    return Ok(trades)
+                },
+                _ => {},
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    // Build a city on the village with the most pips, otherwise the village that adds the most to
+    // our production, otherwise the street that makes our road the longest
+    fn build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+        let me = get_me(game)?;
+        let scoreboard = Scoreboard::new(graph, game.get_players(), self.random.holders);
+        debug!("We have {} points, about {:.0} turns from winning", scoreboard.victory_points(me), scoreboard.turns_to_win(me));
+
+        let builds: Vec<BuildCommand> = legal_moves(graph, me, ResponseCode::BuildRequest).into_iter().filter_map(|legal_move| {
+            match legal_move {
+                Move::Build(build) if !self.random.is_rejected(game, &build.location) => Some(build),
+                _ => None,
+            }
+        }).collect();
+        let (cities, builds): (Vec<BuildCommand>, Vec<BuildCommand>) = builds.into_iter()
+            .partition(|build| build.structure == StructureKind::City);
+        if let Some(best_city) = get_best_city(graph, cities) {
+            return Ok(vec!(best_city))
+        }
+
+        let (villages, streets): (Vec<BuildCommand>, Vec<BuildCommand>) = builds.into_iter()
+            .partition(|build| build.structure == StructureKind::Village);
+        let nodes: Vec<&Node> = villages.iter().filter_map(|village| graph.get_node_by_key(&village.location)).collect();
+        if let Some(village) = PlacementEvaluator::new(graph).get_best_village(me, nodes) {
+            return Ok(vec!(BuildCommand { structure: StructureKind::Village, location: village.key.clone() }))
+        }
+
+        match graph.get_best_road_extension(me) {
+            Some((street, length)) if streets.iter().any(|build| build.location == street.key) => {
+                debug!("Building a street at {}, our road grows from {} to {}", street.key, graph.get_longest_road(me), length);
+                Ok(vec!(BuildCommand { structure: StructureKind::Street, location: street.key.clone() }))
+            },
+            _ => Ok(streets.into_iter().take(1).collect()),
+        }
+    }
+
+    // Place the village on the spot with the best production and a street towards the best spot
+    // for the next village
+    fn initial_build(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuildCommand>, ClientError> {
+        let me = get_me(game)?;
+        let evaluator = PlacementEvaluator::new(graph);
+        match evaluator.get_best_placement(me, game.get_players().len()) {
+            Some((village, street)) if !self.random.is_rejected(game, &village.key) => {
+                debug!("Placing a village at {} with a score of {}", village.key, evaluator.score_node(village, &[]));
+                Ok(vec!(
+                    BuildCommand { structure: StructureKind::Village, location: village.key.clone() },
+                    BuildCommand { structure: StructureKind::Street, location: street.key.clone() },
+                ))
+            },
+            _ => self.random.initial_build(game, graph),
+        }
+    }
+
+    // Block the most production of the leading opponent, away from our own buildings
+    fn move_bandit(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<MoveBanditCommand>, ClientError> {
+        let me = get_me(game)?;
+        match self.get_bandit_target(game, graph, me) {
+            Some(target) => {
+                debug!("Moving the bandit to {} with a score of {}, stealing from {:?}", target.tile.key, target.score, target.victim);
+                Ok(vec!(MoveBanditCommand { location: target.tile.key.clone() }))
+            },
+            None => self.random.move_bandit(game, graph),
+        }
+    }
+
+    // Keep the cards for the next build, discard what we have most of
+    fn discard(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<Resource>, ClientError> {
+        let me = get_me(game)?;
+        let discard = plan_discard(me);
+        if self.random.is_rejected_discard(game, &discard) {
+            return self.random.discard(game, graph)
+        }
+        debug!("Discarding {:?} of {:?}", discard, me.resources);
+        self.random.last_discard = discard.clone();
+        Ok(discard)
+    }
+
+    // Play a knight when the bandit blocks us, otherwise the card that helps us build, otherwise a
+    // knight if it gets us the largest army
+    fn play_development_card(&mut self, game: &Game, graph: &BoardGraph) -> Result<Option<PlayDevelopmentCardCommand>, ClientError> {
+        let me = get_me(game)?;
+        let has = |card: DevelopmentCardKind| me.get_development_card_count(card) > 0;
+
+        let knight = if has(DevelopmentCardKind::Knight) {
+            self.get_bandit_target(game, graph, me).map(|target| PlayDevelopmentCardCommand::Knight { location: target.tile.key.clone() })
+        } else {
+            None
+        };
+        let is_blocked = graph.get_board().get_bandits().into_iter()
+            .filter_map(|bandit| graph.get_tile_by_key(&bandit.tile_key))
+            .any(|tile| graph.get_nodes_surrounding_tile(tile).into_iter().any(|node| node.player == Some(me.id)));
+        if is_blocked && knight.is_some() {
+            return Ok(knight)
+        }
+
+        if has(DevelopmentCardKind::YearOfPlenty) {
+            if let Some(resources) = self.get_year_of_plenty(graph, me) {
+                return Ok(Some(PlayDevelopmentCardCommand::YearOfPlenty { resources }))
+            }
+        }
+        if has(DevelopmentCardKind::Monopoly) {
+            if let Some(resource) = self.get_monopoly(game, me) {
+                return Ok(Some(PlayDevelopmentCardCommand::Monopoly { resource }))
+            }
+        }
+        if has(DevelopmentCardKind::RoadBuilding) {
+            let streets: Vec<String> = graph.get_road_extensions(me).into_iter().take(2).map(|(edge, _)| edge.key.clone()).collect();
+            if !streets.is_empty() {
+                return Ok(Some(PlayDevelopmentCardCommand::RoadBuilding { streets }))
+            }
+        }
+
+        let wins_army = game.get_players().into_iter().all(|player| player.id == me.id || player.played_knights <= me.played_knights)
+            && me.played_knights + 1 >= MIN_LARGEST_ARMY;
+        let has_army = Scoreboard::new(graph, game.get_players(), self.random.holders).get_largest_army_holder() == Some(me.id);
+        Ok(if wins_army && !has_army { knight } else { None })
+    }
+
+    // Spend what is left after building on a development card, unless we are saving for a city
+    fn buy_development_cards(&mut self, game: &Game, graph: &BoardGraph) -> Result<Vec<BuyDevelopmentCardCommand>, ClientError> {
+        let me = get_me(game)?;
+        let missing_for_city: usize = me.missing_for(StructureKind::City).iter().map(|resource| resource.value).sum();
+        let saving_for_city = has_location(graph, me, StructureKind::City) && missing_for_city <= 1;
+        if me.can_afford_development_card() && !saving_for_city {
+            return Ok(vec!(BuyDevelopmentCardCommand {}))
+        }
+        Ok(Vec::new())
+    }
+
+    fn rejected(&mut self, game: &Game, rejection: &Rejection) {
+        self.random.rejected(game, rejection)
+    }
+
+    fn observe(&mut self, game: &Game, graph: &BoardGraph) {
+        self.random.observe(game, graph)
+    }
+}
+
+// whether there is a place where we could build the structure
+fn has_location(graph: &BoardGraph, me: &Player, structure: StructureKind) -> bool {
+    match structure {
+        StructureKind::City => !graph.get_villages_from_player(me).is_empty(),
+        StructureKind::Village => !graph.get_potential_village_nodes(me).is_empty(),
+        StructureKind::Street => !graph.get_potential_street_edges(me).is_empty(),
+    }
+}
+
+// Our own player, strategies cannot decide anything before our id is known
+fn get_me(game: &Game) -> Result<&Player, ClientError> {
+    game.me().ok_or_else(|| {
+        debug!("game: {:?}", game.get_players());
+        ClientError::MissingState("me() does not exist at this point?")
+    })
+}
+
+// The city upgrade on the village that produces most often
+fn get_best_city(graph: &BoardGraph, cities: Vec<BuildCommand>) -> Option<BuildCommand> {
+    cities.into_iter().max_by_key(|city| {
+        graph.get_node_by_key(&city.location).map_or(0, |node| graph.get_pips_of_node(node))
+    })
+}
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use crate::board::*;
+use crate::commands::*;
+
+// Trades with the bank and the ports. The bank gives one resource for four of another, a generic
+// port for three and a resource port for two of its own resource.
+
+pub const BANK_TRADE_RATIO: usize = 4;
+
+// The best ratio a player gets for every resource
+pub struct TradeRates {
+    ratios: HashMap<ResourceKind, usize>,
+}
+
+impl TradeRates {
+    pub fn new(ports: &[&Port]) -> TradeRates {
+        let ratios = ResourceKind::ALL.iter().map(|resource| {
+            let ratio = ports.iter().filter_map(|port| port.get_ratio(*resource)).min().unwrap_or(BANK_TRADE_RATIO);
+            (*resource, ratio.min(BANK_TRADE_RATIO))
+        }).collect();
+        TradeRates { ratios }
+    }
+
+    pub fn for_player(board: &Board, player: &Player) -> TradeRates {
+        TradeRates::new(&board.get_ports_for_player(player))
+    }
+
+    pub fn get_ratio(&self, resource: ResourceKind) -> usize {
+        self.ratios.get(&resource).cloned().unwrap_or(BANK_TRADE_RATIO)
+    }
+}
+
+// The trades that make the structure affordable while giving away as few cards as possible, None
+// if we cannot get there by trading. Every trade pays with the resource we have the best ratio
+// for, and the most spare cards of, that the structure does not need.
+pub fn plan_trades(player: &Player, rates: &TradeRates, structure: StructureKind) -> Option<Vec<TradeCommand>> {
+    let cost: HashMap<ResourceKind, usize> = structure.get_cost().into_iter().collect();
+    let needed = |resource: &ResourceKind| cost.get(resource).cloned().unwrap_or(0);
+    let mut hand: HashMap<ResourceKind, usize> = ResourceKind::ALL.iter().map(|resource| (*resource, player.get_resource_count(*resource))).collect();
+    let mut trades = Vec::new();
+
+    loop {
+        let missing = match ResourceKind::ALL.iter().cloned().find(|resource| hand[resource] < needed(resource)) {
+            Some(missing) => missing,
+            None => return Some(trades),
+        };
+        let from = ResourceKind::ALL.iter().cloned()
+            .filter(|resource| *resource != missing && hand[resource] >= needed(resource) + rates.get_ratio(*resource))
+            .min_by_key(|resource| (rates.get_ratio(*resource), std::cmp::Reverse(hand[resource] - needed(resource))))?;
+        *hand.get_mut(&from).unwrap() -= rates.get_ratio(from);
+        *hand.get_mut(&missing).unwrap() += 1;
+        trades.push(TradeCommand { from, to: missing });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::tests::*;
+
+    fn get_player_with(resources: &[(ResourceKind, usize)]) -> Player {
+        let mut player = get_player(0);
+        player.resources = resources.iter().map(|(resource, value)| Resource { r#type: *resource, value: *value }).collect();
+        player
+    }
+
+    fn get_port(kind: PortKind) -> Port {
+        Port { edge_key: path_edge(0), kind, ratio: None }
+    }
+
+    fn trade(from: ResourceKind, to: ResourceKind) -> TradeCommand {
+        TradeCommand { from, to }
+    }
+
+    #[test]
+    fn trades_four_for_one_with_the_bank() {
+        let rates = TradeRates::new(&[]);
+        let player = get_player_with(&[(ResourceKind::Wood, 1), (ResourceKind::Ore, 4)]);
+        assert_eq!(plan_trades(&player, &rates, StructureKind::Street), Some(vec!(trade(ResourceKind::Ore, ResourceKind::Stone))));
+        // the wood is needed for the street itself
+        let player = get_player_with(&[(ResourceKind::Wood, 2), (ResourceKind::Ore, 3)]);
+        assert_eq!(plan_trades(&player, &rates, StructureKind::Street), None);
+    }
+
+    #[test]
+    fn trades_three_for_one_at_a_generic_port() {
+        let port = get_port(PortKind::Generic);
+        let rates = TradeRates::new(&[&port]);
+        let player = get_player_with(&[(ResourceKind::Wood, 1), (ResourceKind::Ore, 3)]);
+        assert_eq!(plan_trades(&player, &rates, StructureKind::Street), Some(vec!(trade(ResourceKind::Ore, ResourceKind::Stone))));
+    }
+
+    #[test]
+    fn prefers_a_resource_port() {
+        let generic = get_port(PortKind::Generic);
+        let wool = get_port(PortKind::Resource(ResourceKind::Wool));
+        let rates = TradeRates::new(&[&generic, &wool]);
+        assert_eq!(rates.get_ratio(ResourceKind::Wool), RESOURCE_PORT_RATIO);
+        // the ore would do at the generic port as well, but the wool is cheaper
+        let player = get_player_with(&[(ResourceKind::Wood, 1), (ResourceKind::Ore, 3), (ResourceKind::Wool, 2)]);
+        assert_eq!(plan_trades(&player, &rates, StructureKind::Street), Some(vec!(trade(ResourceKind::Wool, ResourceKind::Stone))));
+    }
+
+    #[test]
+    fn gives_up_when_trading_does_not_get_there() {
+        let port = get_port(PortKind::Generic);
+        let rates = TradeRates::new(&[&port]);
+        // one trade gets the stone, nothing is left to get the grain and the wool
+        let player = get_player_with(&[(ResourceKind::Wood, 1), (ResourceKind::Ore, 5)]);
+        assert_eq!(plan_trades(&player, &rates, StructureKind::Village), None);
+        assert_eq!(plan_trades(&get_player_with(&[]), &rates, StructureKind::Street), None);
+    }
+}